@@ -1,12 +1,92 @@
 import React, { useEffect, useState } from 'react'
 import './App.css'
-import { Question, Statistics, newQuestion, submitAnswer, todayScore } from './api'
+import { MathText } from './math'
+import { Question, Session, Statistics, newQuestion, newSession, submitAnswer, todayScore } from './api'
+
+// Times tables to drill, e.g. `/?tables=6,7,8`
+function timesTables(): number[] {
+    const tables = new URLSearchParams(window.location.search).get("tables");
+    return tables ? tables.split(",").map(Number).filter((n) => n > 0) : [];
+}
+
+// Seed of the session, e.g. `/?seed=1234` asks the same questions every time
+function seed(): number | undefined {
+    const seed = new URLSearchParams(window.location.search).get("seed");
+    return seed ? Number(seed) : undefined;
+}
+
+// Tap to choose instead of typing, e.g. `/?choices=1`
+function multipleChoice(): boolean {
+    return new URLSearchParams(window.location.search).get("choices") === "1";
+}
 
 function QuestionDisplay(question: Question) {
+    if (question.kind === "missing_operand" || question.kind === "convert_units") {
+        // The answer goes in place of the blank
+        const [before, after] = question.question.split("?");
+        return (
+            <div style={{ fontSize: "2.25rem" }}
+                className='text-2xl font-mono font-black text-left bg-green-300 border-0 py-4 px-3 rounded text-base mt-10'>
+                {MathText(before)}<span className='inline-block min-w-16 text-center border-b-4 border-green-800'>{question.answer}</span>{MathText(after)}
+            </div>
+        );
+    }
+    if (question.kind === "compare" || question.kind === "compare_unit_prices") {
+        // The chosen relation goes into the box
+        return (
+            <div style={{ fontSize: "2.25rem" }}
+                className='text-2xl font-mono font-black text-left bg-green-300 border-0 py-4 px-3 rounded text-base mt-10'>
+                {MathText(question.question.replace("☐", question.answer ?? "☐"))}
+            </div>
+        );
+    }
+    if (question.kind === "sequence") {
+        // One box per term, the answer goes into the last one
+        const terms = question.question.split(", ");
+        return (
+            <div style={{ fontSize: "2.25rem" }}
+                className='flex flex-wrap gap-2 text-2xl font-mono font-black bg-green-300 border-0 py-4 px-3 rounded text-base mt-10'>
+                {terms.map((term, i) => (
+                    <span key={i} className='inline-block min-w-16 text-center border-4 border-green-800 rounded'>
+                        {term === "?" ? (question.answer ?? "?") : term}
+                    </span>
+                ))}
+            </div>
+        );
+    }
+    if (question.kind === "equation") {
+        // The answer is the solution, the question already has its own `=`
+        return (
+            <div style={{ fontSize: "2.25rem" }}
+                className='text-2xl font-mono font-black text-left bg-green-300 border-0 py-4 px-3 rounded text-base mt-10'>
+                {MathText(question.question)}, x = {question.answer}
+            </div>
+        );
+    }
+    if (question.kind === "word_problem") {
+        // A sentence, not math, the answer goes on its own line
+        return (
+            <div className='text-2xl font-black text-left bg-green-300 border-0 py-4 px-3 rounded text-base mt-10'>
+                <p>{question.question}</p>
+                <p style={{ fontSize: "2.25rem" }} className='font-mono'>= {question.answer}</p>
+            </div>
+        );
+    }
+    if (question.answer_type === "quotient_remainder") {
+        // Separate fields for the quotient and the remainder
+        const [quotient, remainder] = (question.answer ?? "").split("R");
+        return (
+            <div style={{ fontSize: "2.25rem" }}
+                className='text-2xl font-mono font-black text-left bg-green-300 border-0 py-4 px-3 rounded text-base mt-10'>
+                {MathText(question.question)} = <span className='inline-block min-w-16 border-b-4 border-green-800'>{quotient}</span>
+                {" R "}<span className='inline-block min-w-16 border-b-4 border-green-800'>{remainder}</span>
+            </div>
+        );
+    }
     return (
         <div style={{ fontSize: "2.25rem" }}
             className='text-2xl font-mono font-black text-left bg-green-300 border-0 py-4 px-3 rounded text-base mt-10'>
-            {question.question} = {question.answer}
+            {MathText(question.question)} = {question.answer}{question.kind === "what_percent" ? "%" : ""}
         </div>
     );
 }
@@ -47,33 +127,91 @@ function ScoreDisplay(stat: Statistics) {
 }
 
 function NumberButton(digit: number, question: Question, setQuestion: React.Dispatch<React.SetStateAction<Question>>) {
+    // Choices are picked with their own buttons
+    const choice = question.answer_type === "boolean" || question.answer_type === "relation";
     return (
         <button
             className='text-4xl text-center text-white bg-green-800 border-0 py-4 px-3 focus:outline-none hover:bg-green-700 rounded mt-4 md:mt-4'
+            disabled={choice}
             onClick={() => {
+                // No leading zeros
+                const answer = question.answer === "0" ? "" : question.answer === "-0" ? "-" : (question.answer ?? "");
                 setQuestion({
-                    id: question.id,
-                    question: question.question,
-                    answer: (question.answer ?? 0) * 10 + digit
+                    ...question,
+                    answer: answer + digit.toString(),
                 });
             }}>{digit}</button>
     );
 }
 
+function SymbolButton(symbol: string, answerType: string, question: Question, setQuestion: React.Dispatch<React.SetStateAction<Question>>) {
+    if (question.answer_type !== answerType) {
+        return (<></>);
+    }
+    // Only one fraction bar, decimal point, remainder separator or colon, and only after a digit,
+    // lists take a separator after every number
+    const enabled = (question.answer !== undefined) && (question.answer !== "") &&
+        (answerType === "integer_list" ? /\d$/.test(question.answer) : !/[/.R:]/.test(question.answer));
+    return (
+        <button
+            className='text-4xl text-center text-white bg-green-800 border-0 py-4 px-3 focus:outline-none hover:bg-green-700 rounded mt-4 md:mt-4'
+            disabled={!enabled}
+            onClick={() => {
+                setQuestion({
+                    ...question,
+                    answer: (question.answer ?? "") + symbol,
+                });
+            }}>{symbol}</button>
+    );
+}
+
+function ChoiceButton(label: string, value: string, answerType: string, question: Question, setQuestion: React.Dispatch<React.SetStateAction<Question>>) {
+    if (question.answer_type !== answerType) {
+        return (<></>);
+    }
+    return (
+        <button
+            className='text-4xl text-center text-white bg-green-800 border-0 py-4 px-3 focus:outline-none hover:bg-green-700 rounded mt-4 md:mt-4'
+            onClick={() => {
+                setQuestion({
+                    ...question,
+                    answer: value,
+                });
+            }}>{label}</button>
+    );
+}
+
+function SignButton(question: Question, setQuestion: React.Dispatch<React.SetStateAction<Question>>) {
+    if (!question.negative_numbers || question.answer_type !== "integer") {
+        return (<></>);
+    }
+    return (
+        <button
+            className='text-4xl text-center text-white bg-green-800 border-0 py-4 px-3 focus:outline-none hover:bg-green-700 rounded mt-4 md:mt-4'
+            onClick={() => {
+                // Toggles the sign, also before the first digit
+                const answer = question.answer ?? "";
+                setQuestion({
+                    ...question,
+                    answer: answer.startsWith("-") ? (answer.slice(1) || undefined) : "-" + answer,
+                });
+            }}>-</button>
+    );
+}
+
 function Backspace(question: Question, setQuestion: React.Dispatch<React.SetStateAction<Question>>) {
-    let newAnswer: number | undefined = undefined;
-    if ((question.answer === undefined) || (question.answer < 10)) {
+    let newAnswer: string | undefined = undefined;
+    if ((question.answer === undefined) || (question.answer.length <= 1)) {
         newAnswer = undefined;
     } else {
-        newAnswer = Math.floor((question.answer) / 10);
+        newAnswer = question.answer.slice(0, -1);
     }
     return (
         <button style={{ fontSize: "1.875rem" }}
             className='flex justify-center items-center text-white bg-red-800 border-0 py-2 px-2 focus:outline-none hover:bg-red-700 rounded text-base mt-4 md:mt-4 col-span-2'
             onClick={() => {
                 setQuestion({
-                    id: question.id,
-                    question: question.question,
+                    ...question,
                     answer: newAnswer,
                 });
             }}>
@@ -86,26 +224,45 @@ function Backspace(question: Question, setQuestion: React.Dispatch<React.SetStat
     );
 }
 
-function SubmitButton(question: Question, setQuestion: React.Dispatch<React.SetStateAction<Question>>, setStat: React.Dispatch<React.SetStateAction<Statistics>>, setCorrect: React.Dispatch<React.SetStateAction<boolean | undefined>>) {
+function submit(answer: string, session: Session | undefined, question: Question, setQuestion: React.Dispatch<React.SetStateAction<Question>>, setStat: React.Dispatch<React.SetStateAction<Statistics>>, setCorrect: React.Dispatch<React.SetStateAction<boolean | undefined>>) {
+    console.log(`Submitting ${question.id} ${answer}`);
+    submitAnswer(question.id, answer).then((result) => {
+        setCorrect(result.correct);
+        setTimeout(() => {
+            setCorrect(undefined);
+        }, 2000);
+        todayScore().then(setStat).catch(console.error);
+        newQuestion(session?.id, timesTables(), multipleChoice()).then(setQuestion).catch(console.error);
+    }).catch(console.error);
+}
+
+function SubmitButton(session: Session | undefined, question: Question, setQuestion: React.Dispatch<React.SetStateAction<Question>>, setStat: React.Dispatch<React.SetStateAction<Statistics>>, setCorrect: React.Dispatch<React.SetStateAction<boolean | undefined>>) {
     return (
         <button style={{ fontSize: "1.875rem" }}
             className='text-2xl text-center text-white bg-blue-800 border-0 py-4 px-3 focus:outline-none hover:bg-blue-700 rounded text-base mt-4 md:mt-4 col-start-4 col-span-2'
             onClick={() => {
-                if (question.answer !== undefined) {
-                    console.log(`Submitting ${question.id} ${question.answer}`);
-                    submitAnswer(question.id, question.answer!).then((result) => {
-                        setCorrect(result.correct);
-                        setTimeout(() => {
-                            setCorrect(undefined);
-                        }, 2000);
-                        todayScore().then(setStat).catch(console.error);
-                        newQuestion().then(setQuestion).catch(console.error);
-                    }).catch(console.error);
+                if (question.answer !== undefined && question.answer !== "-") {
+                    submit(question.answer, session, question, setQuestion, setStat, setCorrect);
                 }
             }}>提交</button>
     );
 }
 
+// One big button per choice, tapping it submits the answer
+function ChoiceList(session: Session | undefined, question: Question, setQuestion: React.Dispatch<React.SetStateAction<Question>>, setStat: React.Dispatch<React.SetStateAction<Statistics>>, setCorrect: React.Dispatch<React.SetStateAction<boolean | undefined>>) {
+    return (
+        <div className='grid gap-4 grid-cols-2 mt-10'>
+            {(question.choices ?? []).map((choice) => (
+                <button key={choice}
+                    className='text-4xl text-center text-white bg-green-800 border-0 py-6 px-3 focus:outline-none hover:bg-green-700 rounded'
+                    onClick={() => {
+                        submit(choice, session, question, setQuestion, setStat, setCorrect);
+                    }}>{MathText(choice)}</button>
+            ))}
+        </div>
+    );
+}
+
 function ResultOverlay(correct: boolean | undefined) {
     if (correct === undefined) {
         return (<></>);
@@ -130,11 +287,15 @@ function App() {
         question: "Loading...",
     } as Question);
     const [correct, setCorrect] = useState<boolean | undefined>(undefined);
+    const [session, setSession] = useState<Session | undefined>(undefined);
     useEffect(() => {
         todayScore().then(setStat).catch(console.error);
     }, []);
     useEffect(() => {
-        newQuestion().then(setQuestion).catch(console.error);
+        newSession(seed()).then((session) => {
+            setSession(session);
+            return newQuestion(session.id, timesTables(), multipleChoice());
+        }).then(setQuestion).catch(console.error);
     }, []);
 
     return (
@@ -142,25 +303,41 @@ function App() {
             {ResultOverlay(correct)}
             <div className='col-start-1 row-start-1'>
                 {QuestionDisplay(question)}
-                <div className='grid gap-4 grid-cols-5 mt-10'>
-                    {NumberButton(1, question, setQuestion)}
-                    {NumberButton(2, question, setQuestion)}
-                    {NumberButton(3, question, setQuestion)}
-                    {NumberButton(4, question, setQuestion)}
-                    {NumberButton(5, question, setQuestion)}
-                </div>
-                <div className='grid gap-4 grid-cols-5 mt-10'>
-                    {NumberButton(6, question, setQuestion)}
-                    {NumberButton(7, question, setQuestion)}
-                    {NumberButton(8, question, setQuestion)}
-                    {NumberButton(9, question, setQuestion)}
-                    {NumberButton(0, question, setQuestion)}
-                </div>
-                <div className='grid gap-4 grid-cols-5 mt-10'>
-                    {Backspace(question, setQuestion)}
-                    {SubmitButton(question, setQuestion, setStat, setCorrect)}
-                </div>
+                {question.choices?.length ? ChoiceList(session, question, setQuestion, setStat, setCorrect) : <>
+                    <div className='grid gap-4 grid-cols-5 mt-10'>
+                        {NumberButton(1, question, setQuestion)}
+                        {NumberButton(2, question, setQuestion)}
+                        {NumberButton(3, question, setQuestion)}
+                        {NumberButton(4, question, setQuestion)}
+                        {NumberButton(5, question, setQuestion)}
+                    </div>
+                    <div className='grid gap-4 grid-cols-5 mt-10'>
+                        {NumberButton(6, question, setQuestion)}
+                        {NumberButton(7, question, setQuestion)}
+                        {NumberButton(8, question, setQuestion)}
+                        {NumberButton(9, question, setQuestion)}
+                        {NumberButton(0, question, setQuestion)}
+                    </div>
+                    <div className='grid gap-4 grid-cols-5 mt-10'>
+                        {Backspace(question, setQuestion)}
+                        {SymbolButton("/", "fraction", question, setQuestion)}
+                        {SymbolButton(".", "decimal", question, setQuestion)}
+                        {SymbolButton(".", "money", question, setQuestion)}
+                        {SymbolButton("R", "quotient_remainder", question, setQuestion)}
+                        {SymbolButton(":", "ratio", question, setQuestion)}
+                        {SymbolButton(":", "time", question, setQuestion)}
+                        {SymbolButton("x", "integer_list", question, setQuestion)}
+                        {ChoiceButton("是", "yes", "boolean", question, setQuestion)}
+                        {ChoiceButton("否", "no", "boolean", question, setQuestion)}
+                        {ChoiceButton("<", "<", "relation", question, setQuestion)}
+                        {ChoiceButton("=", "=", "relation", question, setQuestion)}
+                        {ChoiceButton(">", ">", "relation", question, setQuestion)}
+                        {SignButton(question, setQuestion)}
+                        {SubmitButton(session, question, setQuestion, setStat, setCorrect)}
+                    </div>
+                </>}
                 {ScoreDisplay(stat)}
+                {session && <div className='mt-5 text-sm text-center text-gray-500'>种子：{session.seed}</div>}
                 <div className='mt-5 text-3xl text-center'>
                     <a href="/last7"
                         className='mr-5 text-white bg-blue-700 hover:bg-blue-800 focus:ring-4 focus:ring-blue-300 font-medium rounded-lg text-sm px-5 py-2.5 dark:bg-blue-600 dark:hover:bg-blue-700 focus:outline-none dark:focus:ring-blue-800"'>最近7天</a>
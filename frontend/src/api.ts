@@ -3,7 +3,18 @@ const API_BASE = import.meta.env.VITE_API_BASE as string;
 export interface Question {
     id: number;
     question: string;
-    answer?: number;
+    kind?: string;
+    answer?: string;
+    answer_type?: string;
+    wrong_parts?: string[];
+    negative_numbers?: boolean;
+    // Answers to tap in multiple choice mode
+    choices?: string[];
+}
+
+export interface Session {
+    id: number;
+    seed: number;
 }
 
 export interface SubmitResponse {
@@ -27,13 +38,34 @@ export interface MultiStatistics {
     overall: Statistics,
 }
 
-export async function newQuestion(): Promise<Question> {
+export interface RegroupingStatistics {
+    regrouping: string,
+    regroupings: number,
+    total: number,
+    correct: number,
+}
+
+export async function newSession(seed?: number): Promise<Session> {
+    return (await fetch(`${API_BASE}/new-session`, {
+        method: "POST",
+        headers: {
+            "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ seed }),
+    })).json();
+}
+
+export async function newQuestion(session?: number, timesTables?: number[], multipleChoice?: boolean): Promise<Question> {
     return (await fetch(`${API_BASE}/new-question`, {
         method: "POST",
+        headers: {
+            "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ session, times_tables: timesTables, multiple_choice: multipleChoice }),
     })).json();
 }
 
-export async function submitAnswer(questionId: number, answer: number): Promise<SubmitResponse> {
+export async function submitAnswer(questionId: number, answer: string): Promise<SubmitResponse> {
     return (await fetch(`${API_BASE}/submit-answer`, {
         method: "POST",
         headers: {
@@ -57,3 +89,7 @@ export async function lastNScore(n: number): Promise<MultiStatistics> {
 export async function mistakeCollection(): Promise<Question[]> {
     return (await fetch(`${API_BASE}/mistake-collection`)).json();
 }
+
+export async function regroupingStatistics(): Promise<RegroupingStatistics[]> {
+    return (await fetch(`${API_BASE}/regrouping`)).json();
+}
@@ -12,6 +12,7 @@ import {
     Last30Days,
 } from './lastN.tsx';
 import {Mistakes} from "./mistakes.tsx";
+import {Regrouping} from "./regrouping.tsx";
 
 const router = createBrowserRouter([
     {
@@ -33,6 +34,10 @@ const router = createBrowserRouter([
     {
         path: "mistakes",
         element: <Mistakes/>,
+    },
+    {
+        path: "regrouping",
+        element: <Regrouping/>,
     }
 ]);
 
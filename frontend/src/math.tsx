@@ -0,0 +1,27 @@
+import React from "react";
+
+const SUPERSCRIPT_DIGITS = "⁰¹²³⁴⁵⁶⁷⁸⁹";
+
+// A radical with the radicand under the bar, or a run of superscript digits
+const MATH_PATTERN = /([√∛])(\([^()]*\)|[\d.]+)|([⁰¹²³⁴⁵⁶⁷⁸⁹]+)/g;
+
+// Renders the question text with real superscripts and radicals, e.g. `7²` or `√144`
+export function MathText(text: string) {
+    const parts: React.ReactNode[] = [];
+    let last = 0;
+    for (const match of text.matchAll(MATH_PATTERN)) {
+        const index = match.index ?? 0;
+        parts.push(text.slice(last, index));
+        if (match[3] !== undefined) {
+            const exponent = [...match[3]].map((c) => SUPERSCRIPT_DIGITS.indexOf(c)).join("");
+            parts.push(<sup key={index}>{exponent}</sup>);
+        } else {
+            // The bar replaces the parentheses around the radicand
+            const radicand = match[2].replace(/^\((.*)\)$/, "$1");
+            parts.push(<span key={index}>{match[1]}<span className='border-t-2 border-current'>{radicand}</span></span>);
+        }
+        last = index + match[0].length;
+    }
+    parts.push(text.slice(last));
+    return <>{parts}</>;
+}
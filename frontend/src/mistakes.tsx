@@ -1,5 +1,6 @@
 import {useEffect, useState} from "react";
 import { mistakeCollection, Question} from "./api.ts";
+import {MathText} from "./math.tsx";
 
 export function Mistakes() {
     const [mistakes, setMistakes] = useState<Question[]>([]);
@@ -23,10 +24,12 @@ export function Mistakes() {
                 return <tr key={`tr-${i.toString()}`} className='bg-white dark:bg-gray-800'>
                     <th key={`td-${i}-1`} scope='row'
                         className='px-6 py-4 font-medium text-gray-900 whitespace-nowrap dark:text-white'>
-                        {q.question}
+                        {MathText(q.question)}
                     </th>
                     <td key={`td-${i}-2`} className={'px-6 py-4'}>
                         {q.answer}
+                        {q.wrong_parts?.includes("quotient") ? "（商错误）" : ""}
+                        {q.wrong_parts?.includes("remainder") ? "（余数错误）" : ""}
                     </td>
                 </tr>;
             })}
@@ -6,11 +6,14 @@ use clap::Parser;
 use clap_verbosity::Verbosity;
 use log::info;
 use once_cell::sync::OnceCell;
-use slint::Weak;
+use slint::{Model, ModelRc, SharedString, VecModel, Weak};
 
 slint::include_modules!();
 
 static INSTANCE: OnceCell<quiz_repo::QuizRepo> = OnceCell::new();
+static GENERATORS: OnceCell<quiz_repo::GeneratorRegistry> = OnceCell::new();
+static SESSION: OnceCell<i64> = OnceCell::new();
+static MULTIPLE_CHOICE: OnceCell<bool> = OnceCell::new();
 
 async fn get_new_question(ui: Weak<AppWindow>) -> anyhow::Result<()> {
     let ui_clone = ui.clone();
@@ -18,12 +21,34 @@ async fn get_new_question(ui: Weak<AppWindow>) -> anyhow::Result<()> {
         ui.set_loading_overlay_visible(true);
         ui.set_question("".into());
         ui.set_answer("".into());
+        ui.set_remainder("".into());
+        ui.set_remainder_active(false);
     })?;
     let question = INSTANCE
         .get()
         .ok_or(anyhow::anyhow!("Failed to get instance"))?
-        .new_question()
+        .new_session_question(
+            *SESSION
+                .get()
+                .ok_or(anyhow::anyhow!("Failed to get session"))?,
+            GENERATORS
+                .get()
+                .ok_or(anyhow::anyhow!("Failed to get generators"))?,
+        )
         .await?;
+    let choices: Vec<SharedString> = if MULTIPLE_CHOICE.get().copied().unwrap_or_default() {
+        INSTANCE
+            .get()
+            .ok_or(anyhow::anyhow!("Failed to get instance"))?
+            .multiple_choice(&question)
+            .await?
+            .0
+            .into_iter()
+            .map(|choice| choice.answer.to_string().into())
+            .collect()
+    } else {
+        vec![]
+    };
     ui.upgrade_in_event_loop(move |ui| {
         info!(
             "Got new question, id: {}, question: {}, answer: {:?}",
@@ -32,7 +57,25 @@ async fn get_new_question(ui: Weak<AppWindow>) -> anyhow::Result<()> {
             question.get_answer(),
         );
         ui.set_id(question.get_id().to_string().into());
-        ui.set_question(format!("{} =", question.get_question()).into());
+        ui.set_question_kind(question.get_kind().name().into());
+        if let quiz_repo::QuestionKind::Sequence { terms } = question.get_kind() {
+            let terms: Vec<SharedString> =
+                terms.iter().map(|term| term.to_string().into()).collect();
+            ui.set_sequence_terms(ModelRc::new(VecModel::from(terms)));
+        }
+        ui.set_question_template(question.get_question().into());
+        // Equations already have their own `=`, the answer is the solution
+        let prompt = match question.get_kind() {
+            quiz_repo::QuestionKind::Equation { .. } => {
+                format!("{}, x =", question.get_question())
+            }
+            quiz_repo::QuestionKind::Compare { .. }
+            | quiz_repo::QuestionKind::CompareUnitPrices { .. }
+            | quiz_repo::QuestionKind::ConvertUnits { .. }
+            | quiz_repo::QuestionKind::WordProblem { .. } => question.get_question(),
+            _ => format!("{} =", question.get_question()),
+        };
+        ui.set_question(prompt.into());
         ui.set_answer(
             question
                 .get_answer()
@@ -40,17 +83,61 @@ async fn get_new_question(ui: Weak<AppWindow>) -> anyhow::Result<()> {
                 .unwrap_or_default()
                 .into(),
         );
-        ui.set_number_enabled(ui.get_answer().len() <= 8);
+        ui.set_answer_type(question.get_expected_answer().kind().into());
+        ui.set_choices(ModelRc::new(VecModel::from(choices)));
+        set_input(&ui, ui.get_answer().to_string());
         ui.set_loading_overlay_visible(false);
     })?;
     Ok(())
 }
 
-async fn submit_answer(ui: Weak<AppWindow>, id: i64, answer: i64) -> anyhow::Result<()> {
+/// The text of the answer field that currently takes the input,
+/// this is the remainder field after the "R" key was pressed.
+fn get_input(ui: &AppWindow) -> String {
+    if ui.get_remainder_active() {
+        ui.get_remainder().to_string()
+    } else {
+        ui.get_answer().to_string()
+    }
+}
+
+fn set_input(ui: &AppWindow, input: String) {
+    // Lists like `2x2x2x2x2x2` need more room
+    let max_len = if ui.get_answer_type() == "integer_list" {
+        16
+    } else {
+        8
+    };
+    // Choices are picked with their own keys
+    let choice = matches!(ui.get_answer_type().as_str(), "boolean" | "relation")
+        || ui.get_choices().row_count() > 0;
+    ui.set_number_enabled(!choice && input.len() <= max_len);
+    // The input is shown in place of the blank
+    let blank = match ui.get_question_kind().as_str() {
+        "missing_operand" | "convert_units" => Some('?'),
+        "compare" | "compare_unit_prices" => Some('☐'),
+        _ => None,
+    };
+    if let Some(blank) = blank {
+        let text = if input.is_empty() {
+            blank.to_string()
+        } else {
+            input.clone()
+        };
+        ui.set_question(ui.get_question_template().replacen(blank, &text, 1).into());
+    }
+    if ui.get_remainder_active() {
+        ui.set_remainder(input.into());
+    } else {
+        ui.set_answer(input.into());
+    }
+}
+
+async fn submit_answer(ui: Weak<AppWindow>, id: i64, answer: String) -> anyhow::Result<()> {
     let correct = INSTANCE
         .get()
         .ok_or(anyhow::anyhow!("Failed to get instance"))?
-        .answer_question(id, answer)
+        .answer_question(id, &answer)
         .await?;
     info!("Id: {}, correct: {}", id, correct);
     let ui_clone = ui.clone();
@@ -93,6 +180,46 @@ struct Args {
     #[arg(short, long)]
     db_file: Option<PathBuf>,
 
+    /// School grade (1-6) used to pick the question difficulty.
+    #[arg(short, long)]
+    grade: Option<u8>,
+
+    /// Include negative numbers in the integer questions.
+    #[arg(long)]
+    negative: bool,
+
+    /// Number of carries in addition: "none", "one" or "multiple".
+    #[arg(long)]
+    carries: Option<quiz_repo::RegroupingCount>,
+
+    /// Number of borrows in subtraction: "none", "one" or "multiple".
+    #[arg(long)]
+    borrows: Option<quiz_repo::RegroupingCount>,
+
+    /// Only drill these times tables, e.g. "6,7,8".
+    #[arg(long, value_delimiter = ',')]
+    times_tables: Vec<i64>,
+
+    /// Add imperial units, e.g. "ft" and "in", to the unit conversions.
+    #[arg(long)]
+    imperial: bool,
+
+    /// JSON file with word problem templates, replaces the built-in ones.
+    #[arg(long)]
+    word_problems: Option<PathBuf>,
+
+    /// Language of the word problems and currency of the money questions, e.g. "en" or "zh".
+    #[arg(long, default_value = "en")]
+    locale: String,
+
+    /// Seed of the questions, the same seed asks the same questions.
+    #[arg(short, long)]
+    seed: Option<u32>,
+
+    /// Pick the answer from the correct one and typical errors instead of typing it.
+    #[arg(short, long)]
+    multiple_choice: bool,
+
     /// Verbosity level.
     #[command(flatten)]
     verbose: Verbosity,
@@ -116,37 +243,126 @@ fn main() -> anyhow::Result<()> {
 
     let instance = handle.block_on(quiz_repo::QuizRepo::new(db_path))?;
 
+    let (session, seed) = handle.block_on(instance.new_session(args.seed))?;
+    info!("Started session {}, seed: {}", session, seed);
+
     INSTANCE.set(instance).ok();
+    SESSION.set(session).ok();
+    MULTIPLE_CHOICE.set(args.multiple_choice).ok();
+    let mut profile = if args.times_tables.is_empty() {
+        quiz_repo::DifficultyProfile {
+            negative_numbers: args.negative,
+            carries: args.carries,
+            borrows: args.borrows,
+            ..args
+                .grade
+                .map(quiz_repo::DifficultyProfile::grade)
+                .unwrap_or_default()
+        }
+    } else {
+        quiz_repo::DifficultyProfile::times_tables(args.times_tables)
+    };
+    if let Some(path) = &args.word_problems {
+        profile.word_problem.templates = quiz_repo::WordProblemTemplate::load(path)?;
+        profile.word_problem.weight = profile.word_problem.weight.max(1);
+    }
+    profile.money.currency = quiz_repo::Currency::from_locale(&args.locale);
+    profile.unit.imperial = args.imperial;
+    profile.word_problem.locale = args.locale;
+    profile.validate()?;
+    GENERATORS
+        .set(quiz_repo::GeneratorRegistry::from_profile(&profile))
+        .ok();
 
     let ui = AppWindow::new()?;
+    ui.set_negative_numbers(args.negative);
     handle.spawn(get_new_question(ui.as_weak()));
 
     let weak_ui = ui.as_weak();
     ui.on_num_clicked(move |num| {
         let ui = weak_ui.unwrap();
-        let mut answer = ui.get_answer().parse::<i64>().unwrap_or(0);
-        answer = answer * 10 + (num as i64);
-        ui.set_answer(answer.to_string().into());
-        ui.set_number_enabled(ui.get_answer().len() <= 8);
+        let mut answer = get_input(&ui);
+        // No leading zeros
+        if answer == "0" || answer == "-0" {
+            answer.pop();
+        }
+        answer.push_str(&num.to_string());
+        set_input(&ui, answer);
+    });
+
+    let weak_ui = ui.as_weak();
+    ui.on_symbol_clicked(move |symbol| {
+        let ui = weak_ui.unwrap();
+        let mut answer = get_input(&ui);
+        // "-" toggles the sign, also before the first digit
+        if symbol == "-" {
+            match answer.strip_prefix('-') {
+                Some(rest) => answer = rest.to_string(),
+                None => answer.insert(0, '-'),
+            }
+            set_input(&ui, answer);
+            return;
+        }
+        // "yes", "no" and the relations replace the whole answer
+        if matches!(ui.get_answer_type().as_str(), "boolean" | "relation") {
+            set_input(&ui, symbol.to_string());
+            return;
+        }
+        if answer.is_empty() {
+            return;
+        }
+        // "x" separates the numbers of a list, one after every number
+        if symbol == "x" {
+            if !answer.ends_with('x') {
+                answer.push('x');
+                set_input(&ui, answer);
+            }
+            return;
+        }
+        // "R" moves on to the remainder field
+        if symbol == "R" {
+            ui.set_remainder_active(true);
+            set_input(&ui, get_input(&ui));
+            return;
+        }
+        // Only one fraction bar, decimal point or colon
+        if answer.contains(['/', '.', ':']) {
+            return;
+        }
+        answer.push_str(&symbol);
+        set_input(&ui, answer);
     });
 
     let weak_ui = ui.as_weak();
     ui.on_backspace_clicked(move || {
         let ui = weak_ui.unwrap();
-        let mut answer = ui.get_answer().parse::<i64>().unwrap_or(0);
-        if answer < 10 {
-            ui.set_answer("".into());
-        } else {
-            answer /= 10;
-            ui.set_answer(answer.to_string().into());
+        let mut answer = get_input(&ui);
+        // Go back to the quotient once the remainder is empty
+        if answer.is_empty() && ui.get_remainder_active() {
+            ui.set_remainder_active(false);
+            answer = get_input(&ui);
         }
-        ui.set_number_enabled(ui.get_answer().len() <= 8);
+        answer.pop();
+        set_input(&ui, answer);
+    });
+
+    let weak_ui = ui.as_weak();
+    let choice_handle = handle.clone();
+    ui.on_choice_clicked(move |choice| {
+        let ui = weak_ui.unwrap();
+        let id = ui.get_id().parse::<i64>().unwrap_or(0);
+        choice_handle.spawn(submit_answer(weak_ui.clone(), id, choice.to_string()));
     });
 
     let weak_ui = ui.as_weak();
     ui.on_submit_clicked(move || {
-        let id = weak_ui.unwrap().get_id().parse::<i64>().unwrap_or(0);
-        let answer = weak_ui.unwrap().get_answer().parse::<i64>().unwrap_or(0);
+        let ui = weak_ui.unwrap();
+        let id = ui.get_id().parse::<i64>().unwrap_or(0);
+        let answer = if ui.get_answer_type() == "quotient_remainder" {
+            format!("{} R {}", ui.get_answer(), ui.get_remainder())
+        } else {
+            ui.get_answer().to_string()
+        };
         handle.spawn(submit_answer(weak_ui.clone(), id, answer));
     });
 
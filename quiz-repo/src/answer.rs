@@ -0,0 +1,376 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+
+use crate::{decimal::Decimal, money::Money, rational::Rational};
+
+/// The relation between two values, the answer of comparison questions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Relation {
+    Less,
+    Equal,
+    Greater,
+}
+
+impl Relation {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Relation::Less => "<",
+            Relation::Equal => "=",
+            Relation::Greater => ">",
+        }
+    }
+}
+
+impl From<Ordering> for Relation {
+    fn from(ordering: Ordering) -> Self {
+        match ordering {
+            Ordering::Less => Relation::Less,
+            Ordering::Equal => Relation::Equal,
+            Ordering::Greater => Relation::Greater,
+        }
+    }
+}
+
+/// The expected answer of a question, or the answer given by the user.
+///
+/// Integers are stored as plain SQLite integers, so databases written before other
+/// answer types existed keep working. The other types are stored as JSON text.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Answer {
+    Integer(i64),
+    /// Compared in reduced form, i.e. `2/4` equals `1/2`.
+    Fraction(Rational),
+    /// Compared exactly, i.e. `3.50` equals `3.5`.
+    Decimal(Decimal),
+    /// Division with remainder, both parts are graded.
+    QuotientRemainder {
+        quotient: i64,
+        remainder: i64,
+    },
+    /// A ratio like `2:3`, compared term by term, so `4:6` doesn't equal `2:3`.
+    Ratio(i64, i64),
+    /// A yes or no answer, e.g. whether a number is prime.
+    Boolean(bool),
+    /// Several integers, e.g. the prime factors `2 x 2 x 3 x 7`, compared in order
+    /// unless the grading policy says otherwise.
+    IntegerList(Vec<i64>),
+    /// One of `<`, `=` or `>`, e.g. for `38 + 47 ☐ 9 x 9`.
+    Relation(Relation),
+    /// An amount in the currency of the question, e.g. `¥12.50`, the symbol is optional
+    /// in the input.
+    Money(Money),
+    /// A time of day on the 24-hour clock, e.g. `13:15`.
+    Time {
+        hour: i64,
+        minute: i64,
+    },
+}
+
+impl Answer {
+    /// Short name of the answer type, lets front ends pick a matching keypad.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Answer::Integer(_) => "integer",
+            Answer::Fraction(_) => "fraction",
+            Answer::Decimal(_) => "decimal",
+            Answer::QuotientRemainder { .. } => "quotient_remainder",
+            Answer::Ratio(..) => "ratio",
+            Answer::Boolean(_) => "boolean",
+            Answer::IntegerList(_) => "integer_list",
+            Answer::Relation(_) => "relation",
+            Answer::Money(_) => "money",
+            Answer::Time { .. } => "time",
+        }
+    }
+
+    /// The numeric value, `None` for answers with several parts.
+    pub fn to_rational(&self) -> Option<Rational> {
+        match self {
+            Answer::Integer(n) => Some(Rational::from(*n)),
+            Answer::Fraction(r) => Some(*r),
+            Answer::Decimal(d) => Some(d.to_rational()),
+            Answer::Money(money) => Some(money.to_rational()),
+            _ => None,
+        }
+    }
+
+    /// Names of the parts of `given` that differ from `self`, empty if it's correct.
+    pub fn wrong_parts(&self, given: &Answer) -> Vec<&'static str> {
+        match (self, given) {
+            (
+                Answer::QuotientRemainder {
+                    quotient,
+                    remainder,
+                },
+                Answer::QuotientRemainder {
+                    quotient: given_quotient,
+                    remainder: given_remainder,
+                },
+            ) => {
+                let mut parts = vec![];
+                if quotient != given_quotient {
+                    parts.push("quotient");
+                }
+                if remainder != given_remainder {
+                    parts.push("remainder");
+                }
+                parts
+            }
+            _ if self == given => vec![],
+            _ => vec!["answer"],
+        }
+    }
+
+    /// Parses the user input as an answer of the same type as `self`.
+    pub fn parse_input(&self, input: &str) -> anyhow::Result<Answer> {
+        let input = input.trim();
+        match self {
+            Answer::Integer(_) => {
+                Ok(Answer::Integer(input.parse().map_err(|_| {
+                    anyhow::Error::msg(format!("Invalid integer: {}", input))
+                })?))
+            }
+            Answer::Fraction(_) => Ok(Answer::Fraction(input.parse()?)),
+            Answer::Decimal(_) => Ok(Answer::Decimal(input.parse()?)),
+            Answer::QuotientRemainder { .. } => {
+                // `7 R 3`, the separator is case insensitive and may also be `,`
+                let invalid =
+                    || anyhow::Error::msg(format!("Invalid quotient and remainder: {}", input));
+                let (quotient, remainder) =
+                    input.split_once(['R', 'r', ',']).ok_or_else(invalid)?;
+                Ok(Answer::QuotientRemainder {
+                    quotient: quotient.trim().parse().map_err(|_| invalid())?,
+                    remainder: remainder.trim().parse().map_err(|_| invalid())?,
+                })
+            }
+            Answer::Ratio(..) => {
+                // `2:3`, the full-width colon is accepted too
+                let invalid = || anyhow::Error::msg(format!("Invalid ratio: {}", input));
+                let (antecedent, consequent) = input.split_once([':', '：']).ok_or_else(invalid)?;
+                Ok(Answer::Ratio(
+                    antecedent.trim().parse().map_err(|_| invalid())?,
+                    consequent.trim().parse().map_err(|_| invalid())?,
+                ))
+            }
+            Answer::Boolean(_) => match input.to_lowercase().as_str() {
+                "yes" | "y" | "true" | "是" | "对" => Ok(Answer::Boolean(true)),
+                "no" | "n" | "false" | "否" | "不是" | "错" => Ok(Answer::Boolean(false)),
+                _ => anyhow::bail!("Invalid yes or no: {}", input),
+            },
+            Answer::IntegerList(_) => {
+                // `2 x 2 x 3 x 7`, also separated by `×`, `*`, `,` or spaces
+                let numbers = input
+                    .split(['x', 'X', '×', '*', ',', ' '])
+                    .filter(|part| !part.is_empty())
+                    .map(|part| part.parse())
+                    .collect::<Result<Vec<i64>, _>>()
+                    .map_err(|_| anyhow::Error::msg(format!("Invalid integer list: {}", input)))?;
+                if numbers.is_empty() {
+                    anyhow::bail!("Invalid integer list: {}", input);
+                }
+                Ok(Answer::IntegerList(numbers))
+            }
+            Answer::Relation(_) => match input {
+                "<" => Ok(Answer::Relation(Relation::Less)),
+                "=" => Ok(Answer::Relation(Relation::Equal)),
+                ">" => Ok(Answer::Relation(Relation::Greater)),
+                _ => anyhow::bail!("Invalid relation: {}, expected <, = or >", input),
+            },
+            Answer::Money(money) => Ok(Answer::Money(Money::parse(input, money.currency)?)),
+            Answer::Time { .. } => {
+                // `13:15` or `8:05`, the full-width colon is accepted too,
+                // minutes run to 59 and hours to 23
+                let invalid = || anyhow::Error::msg(format!("Invalid time: {}", input));
+                let (hour, minute) = input.split_once([':', '：']).ok_or_else(invalid)?;
+                let (hour, minute): (i64, i64) = (
+                    hour.trim().parse().map_err(|_| invalid())?,
+                    minute.trim().parse().map_err(|_| invalid())?,
+                );
+                if !(0..24).contains(&hour) || !(0..60).contains(&minute) {
+                    return Err(invalid());
+                }
+                Ok(Answer::Time { hour, minute })
+            }
+        }
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Integer(n) => write!(f, "{}", n),
+            Answer::Fraction(r) => write!(f, "{}", r),
+            Answer::Decimal(d) => write!(f, "{}", d),
+            Answer::QuotientRemainder {
+                quotient,
+                remainder,
+            } => write!(f, "{} R {}", quotient, remainder),
+            Answer::Ratio(antecedent, consequent) => write!(f, "{}:{}", antecedent, consequent),
+            Answer::Boolean(true) => write!(f, "yes"),
+            Answer::Boolean(false) => write!(f, "no"),
+            Answer::IntegerList(numbers) => write!(
+                f,
+                "{}",
+                numbers
+                    .iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" x ")
+            ),
+            Answer::Relation(relation) => write!(f, "{}", relation.symbol()),
+            Answer::Money(money) => write!(f, "{}", money),
+            Answer::Time { hour, minute } => write!(f, "{}:{:02}", hour, minute),
+        }
+    }
+}
+
+impl ToSql for Answer {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        match self {
+            Answer::Integer(n) => Ok(ToSqlOutput::from(*n)),
+            _ => serde_json::to_string(self)
+                .map(ToSqlOutput::from)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e))),
+        }
+    }
+}
+
+impl FromSql for Answer {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Integer(n) => Ok(Answer::Integer(n)),
+            _ => {
+                serde_json::from_str(value.as_str()?).map_err(|e| FromSqlError::Other(Box::new(e)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Currency;
+
+    fn samples() -> Vec<Answer> {
+        vec![
+            Answer::Integer(-42),
+            Answer::Integer(i64::MIN),
+            Answer::Fraction(Rational::new(-3, 4).unwrap()),
+            Answer::Fraction(Rational::from(i64::MIN)),
+            Answer::Decimal("-0.05".parse().unwrap()),
+            Answer::QuotientRemainder {
+                quotient: 7,
+                remainder: 3,
+            },
+            Answer::Ratio(2, 3),
+            Answer::Boolean(true),
+            Answer::Boolean(false),
+            Answer::IntegerList(vec![2, 2, 3, 7]),
+            Answer::Relation(Relation::Less),
+            Answer::Relation(Relation::Equal),
+            Answer::Relation(Relation::Greater),
+            Answer::Money(Money::new(1250, Currency::Cny)),
+            Answer::Money(Money::new(-40, Currency::Usd)),
+            Answer::Time { hour: 8, minute: 5 },
+        ]
+    }
+
+    #[test]
+    fn parses_what_it_displays() {
+        for answer in samples() {
+            assert_eq!(answer.parse_input(&answer.to_string()).unwrap(), answer);
+        }
+    }
+
+    #[test]
+    fn survives_sql_round_trip() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        for answer in samples() {
+            let stored: Answer = conn
+                .query_row("SELECT ?1", [&answer], |row| row.get(0))
+                .unwrap();
+            assert_eq!(stored, answer);
+        }
+    }
+
+    #[test]
+    fn parses_alternative_input() {
+        let parse = |expected: &Answer, input: &str| expected.parse_input(input).unwrap();
+        let fraction = Answer::Fraction(Rational::from(0));
+        assert_eq!(
+            parse(&fraction, "2/4"),
+            Answer::Fraction(Rational::new(1, 2).unwrap())
+        );
+        let division = Answer::QuotientRemainder {
+            quotient: 0,
+            remainder: 0,
+        };
+        assert_eq!(parse(&division, "7r3"), parse(&division, "7 R 3"));
+        assert_eq!(parse(&division, "7, 3"), parse(&division, "7 R 3"));
+        assert_eq!(parse(&Answer::Ratio(0, 0), "2：3"), Answer::Ratio(2, 3));
+        assert_eq!(parse(&Answer::Boolean(false), "是"), Answer::Boolean(true));
+        assert_eq!(
+            parse(&Answer::IntegerList(vec![]), "2×2*3, 7"),
+            Answer::IntegerList(vec![2, 2, 3, 7])
+        );
+        let time = Answer::Time { hour: 0, minute: 0 };
+        assert_eq!(
+            parse(&time, "13：15"),
+            Answer::Time {
+                hour: 13,
+                minute: 15
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let division = Answer::QuotientRemainder {
+            quotient: 0,
+            remainder: 0,
+        };
+        let time = Answer::Time { hour: 0, minute: 0 };
+        for (expected, input) in [
+            (Answer::Integer(0), "1.5"),
+            (Answer::Integer(0), "9223372036854775808"),
+            (Answer::Fraction(Rational::from(0)), "1/0"),
+            (
+                Answer::Fraction(Rational::from(0)),
+                "-9223372036854775808/-1",
+            ),
+            (
+                Answer::Fraction(Rational::from(0)),
+                "1/-9223372036854775808",
+            ),
+            (division, "7"),
+            (Answer::Ratio(0, 0), "2/3"),
+            (Answer::Boolean(true), "maybe"),
+            (Answer::IntegerList(vec![]), " x "),
+            (Answer::Relation(Relation::Equal), "=="),
+            (time.clone(), "24:00"),
+            (time, "8:60"),
+        ] {
+            assert!(expected.parse_input(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn wrong_parts() {
+        let expected = Answer::QuotientRemainder {
+            quotient: 7,
+            remainder: 3,
+        };
+        let given = Answer::QuotientRemainder {
+            quotient: 7,
+            remainder: 2,
+        };
+        assert_eq!(expected.wrong_parts(&given), vec!["remainder"]);
+        assert!(expected.wrong_parts(&expected).is_empty());
+        assert_eq!(
+            Answer::Integer(1).wrong_parts(&Answer::Integer(2)),
+            vec!["answer"]
+        );
+    }
+}
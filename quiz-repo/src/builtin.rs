@@ -0,0 +1,2024 @@
+//! The generators behind `DifficultyProfile`, registered by `GeneratorRegistry::from_profile`.
+
+use std::ops::RangeInclusive;
+
+use rand::{distributions::WeightedIndex, prelude::Distribution, seq::SliceRandom, Rng, RngCore};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    answer::Answer,
+    decimal::Decimal,
+    expr::{Expr, Operator},
+    generator::{GeneratorRegistry, Parameter, ParameterType, QuestionGenerator},
+    grading::Grading,
+    kind::QuestionKind,
+    money::Money,
+    profile::{DifficultyProfile, OperationProfile, RoundingProfile},
+    rational::{gcd, Rational},
+    unit::{Unit, UnitFamily},
+};
+
+const REGROUPING_COUNTS: &[&str] = &["none", "one", "multiple"];
+
+/// Registers the generators in the order of the fields of the profile,
+/// addition comes first so it's the fallback if every weight is 0.
+pub(crate) fn register(registry: &mut GeneratorRegistry, profile: &DifficultyProfile) {
+    for op in 0..4 {
+        registry.register(OperationGenerator {
+            profile: profile.clone(),
+            op,
+        });
+    }
+    registry
+        .register(MultiStepGenerator(profile.clone()))
+        .register(FractionGenerator(profile.clone()))
+        .register(DecimalGenerator(profile.clone()))
+        .register(RemainderGenerator(profile.clone()))
+        .register(MissingOperandGenerator(profile.clone()))
+        .register(TimesTableGenerator(profile.clone()))
+        .register(PowerGenerator(profile.clone()))
+        .register(PercentGenerator(profile.clone()))
+        .register(RatioGenerator(profile.clone()))
+        .register(RoundingGenerator(profile.clone()))
+        .register(EstimationGenerator(profile.clone()))
+        .register(NumberTheoryGenerator(profile.clone()))
+        .register(EquationGenerator(profile.clone()))
+        .register(SequenceGenerator(profile.clone()))
+        .register(ComparisonGenerator(profile.clone()))
+        .register(WordProblemGenerator(profile.clone()))
+        .register(MoneyGenerator(profile.clone()))
+        .register(TimeGenerator(profile.clone()))
+        .register(UnitGenerator(profile.clone()));
+}
+
+fn range<T: Serialize>(range: &RangeInclusive<T>) -> serde_json::Value {
+    json!({ "start": range.start(), "end": range.end() })
+}
+
+fn weight_parameter(weight: u32) -> Parameter {
+    Parameter::new(
+        "weight",
+        "Relative chance of picking the generator, 0 disables it",
+        ParameterType::Integer,
+        json!(weight),
+    )
+}
+
+/// Addition, subtraction, multiplication or division of two integers, e.g. `37 + 85`.
+struct OperationGenerator {
+    profile: DifficultyProfile,
+    /// Index of the operation, in the order of the fields of the profile.
+    op: usize,
+}
+
+impl OperationGenerator {
+    fn operation(&self) -> &OperationProfile {
+        match self.op {
+            0 => &self.profile.addition,
+            1 => &self.profile.subtraction,
+            2 => &self.profile.multiplication,
+            _ => &self.profile.division,
+        }
+    }
+}
+
+impl QuestionGenerator for OperationGenerator {
+    fn name(&self) -> &'static str {
+        ["addition", "subtraction", "multiplication", "division"][self.op]
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let mut parameters = vec![
+            weight_parameter(self.weight()),
+            Parameter::new(
+                "operands",
+                "Range the operands are drawn from, for division the divisor and the quotient",
+                ParameterType::Range,
+                range(&self.operation().operands),
+            ),
+            Parameter::new(
+                "negative_numbers",
+                "Give the operands random signs",
+                ParameterType::Boolean,
+                json!(self.profile.negative_numbers),
+            ),
+        ];
+        match self.op {
+            0 => parameters.push(Parameter::new(
+                "carries",
+                "Number of carries, null doesn't constrain them",
+                ParameterType::Choice(REGROUPING_COUNTS),
+                json!(self.profile.carries.map(|count| count.to_string())),
+            )),
+            1 => parameters.push(Parameter::new(
+                "borrows",
+                "Number of borrows, null doesn't constrain them",
+                ParameterType::Choice(REGROUPING_COUNTS),
+                json!(self.profile.borrows.map(|count| count.to_string())),
+            )),
+            _ => {}
+        }
+        parameters
+    }
+
+    fn weight(&self) -> u32 {
+        self.operation().weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        integer_question(generate_operation(&self.profile, self.op, rng))
+    }
+}
+
+/// Expressions with several operations, e.g. `(35 + 17) x 4 - 12`.
+struct MultiStepGenerator(DifficultyProfile);
+
+impl QuestionGenerator for MultiStepGenerator {
+    fn name(&self) -> &'static str {
+        "multi_step"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let multi_step = &self.0.multi_step;
+        vec![
+            weight_parameter(multi_step.weight),
+            Parameter::new(
+                "terms",
+                "Number of operands in an expression",
+                ParameterType::Range,
+                range(&multi_step.terms),
+            ),
+            Parameter::new(
+                "operands",
+                "Range the operands are drawn from",
+                ParameterType::Range,
+                range(&multi_step.operands),
+            ),
+            Parameter::new(
+                "max_value",
+                "Upper bound of the answer and every intermediate result",
+                ParameterType::Integer,
+                json!(multi_step.max_value),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.multi_step.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        integer_question(generate_multi_step(&self.0, rng))
+    }
+}
+
+/// Fraction arithmetic with proper fractions, e.g. `3/4 + 1/6`.
+struct FractionGenerator(DifficultyProfile);
+
+impl QuestionGenerator for FractionGenerator {
+    fn name(&self) -> &'static str {
+        "fraction"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let fraction = &self.0.fraction;
+        vec![
+            weight_parameter(fraction.weight),
+            Parameter::new(
+                "denominators",
+                "Range of the denominators",
+                ParameterType::Range,
+                range(&fraction.denominators),
+            ),
+            Parameter::new(
+                "unlike_denominators",
+                "Allow operands with different denominators",
+                ParameterType::Boolean,
+                json!(fraction.unlike_denominators),
+            ),
+            Parameter::new(
+                "multiply_divide",
+                "Allow multiplication and division",
+                ParameterType::Boolean,
+                json!(fraction.multiply_divide),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.fraction.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        generate_fraction(&self.0, rng)
+    }
+}
+
+/// Decimal arithmetic, e.g. `3.75 + 12.4`.
+struct DecimalGenerator(DifficultyProfile);
+
+impl QuestionGenerator for DecimalGenerator {
+    fn name(&self) -> &'static str {
+        "decimal"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let decimal = &self.0.decimal;
+        vec![
+            weight_parameter(decimal.weight),
+            Parameter::new(
+                "whole",
+                "Range of the whole part of the operands",
+                ParameterType::Range,
+                range(&decimal.whole),
+            ),
+            Parameter::new(
+                "places",
+                "Maximum number of decimal places of the operands",
+                ParameterType::Integer,
+                json!(decimal.places),
+            ),
+            Parameter::new(
+                "multiply",
+                "Allow multiplication",
+                ParameterType::Boolean,
+                json!(decimal.multiply),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.decimal.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        generate_decimal(&self.0, rng)
+    }
+}
+
+/// Division with remainder, e.g. `47 ÷ 5 = 9 R 2`.
+struct RemainderGenerator(DifficultyProfile);
+
+impl QuestionGenerator for RemainderGenerator {
+    fn name(&self) -> &'static str {
+        "remainder"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            weight_parameter(self.0.remainder.weight),
+            Parameter::new(
+                "operands",
+                "Range of the divisor and the quotient",
+                ParameterType::Range,
+                range(&self.0.remainder.operands),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.remainder.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let remainder = &self.0.remainder;
+        let divisor = rng.gen_range(remainder.operands.clone()).max(2);
+        let quotient = rng.gen_range(remainder.operands.clone());
+        let remainder = rng.gen_range(1..divisor);
+        let dividend = divisor * quotient + remainder;
+        (
+            QuestionKind::Evaluate(Expr::binary(
+                Operator::Div,
+                Expr::num(dividend),
+                Expr::num(divisor),
+            )),
+            Answer::QuotientRemainder {
+                quotient,
+                remainder,
+            },
+        )
+    }
+}
+
+/// One of the single operations with a blank operand instead of the result,
+/// e.g. `? + 37 = 120`.
+struct MissingOperandGenerator(DifficultyProfile);
+
+impl QuestionGenerator for MissingOperandGenerator {
+    fn name(&self) -> &'static str {
+        "missing_operand"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![weight_parameter(self.0.missing_operand)]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.missing_operand
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let profile = &self.0;
+        let weights = [
+            profile.addition.weight,
+            profile.subtraction.weight,
+            profile.multiplication.weight,
+            profile.division.weight,
+        ];
+        let op = WeightedIndex::new(weights)
+            .map(|distribution| distribution.sample(rng))
+            .unwrap_or(0);
+        let expression = generate_operation(profile, op, rng);
+        let blank = rng.gen_range(0..2);
+        let answer = match expression.operands()[blank] {
+            Expr::Num(n) => *n,
+            _ => unreachable!(),
+        };
+        (
+            QuestionKind::MissingOperand { expression, blank },
+            Answer::Integer(answer),
+        )
+    }
+}
+
+/// Multiplication and division facts of selected times tables, e.g. `7 x 8`.
+struct TimesTableGenerator(DifficultyProfile);
+
+impl QuestionGenerator for TimesTableGenerator {
+    fn name(&self) -> &'static str {
+        "times_table"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let times_table = &self.0.times_table;
+        vec![
+            weight_parameter(times_table.weight),
+            Parameter::new(
+                "tables",
+                "The tables to drill",
+                ParameterType::IntegerList,
+                json!(times_table.tables),
+            ),
+            Parameter::new(
+                "max_factor",
+                "Largest factor the tables go up to",
+                ParameterType::Integer,
+                json!(times_table.max_factor),
+            ),
+            Parameter::new(
+                "divide",
+                "Allow division facts",
+                ParameterType::Boolean,
+                json!(times_table.divide),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.times_table.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        integer_question(generate_times_table(&self.0, rng))
+    }
+}
+
+/// Powers and roots of perfect powers, e.g. `2⁵` or `√144`.
+struct PowerGenerator(DifficultyProfile);
+
+impl QuestionGenerator for PowerGenerator {
+    fn name(&self) -> &'static str {
+        "power"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let power = &self.0.power;
+        vec![
+            weight_parameter(power.weight),
+            Parameter::new(
+                "bases",
+                "Range of the bases of powers and of the values of roots",
+                ParameterType::Range,
+                range(&power.bases),
+            ),
+            Parameter::new(
+                "max_exponent",
+                "Largest exponent",
+                ParameterType::Integer,
+                json!(power.max_exponent),
+            ),
+            Parameter::new(
+                "max_value",
+                "Upper bound of the powers and of the numbers under a root",
+                ParameterType::Integer,
+                json!(power.max_value),
+            ),
+            Parameter::new(
+                "square_roots",
+                "Allow square roots of perfect squares",
+                ParameterType::Boolean,
+                json!(power.square_roots),
+            ),
+            Parameter::new(
+                "cube_roots",
+                "Allow cube roots of perfect cubes",
+                ParameterType::Boolean,
+                json!(power.cube_roots),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.power.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        integer_question(generate_power(&self.0, rng))
+    }
+}
+
+/// Percentages of a number, or the percentage one number is of another.
+struct PercentGenerator(DifficultyProfile);
+
+impl QuestionGenerator for PercentGenerator {
+    fn name(&self) -> &'static str {
+        "percent"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let percent = &self.0.percent;
+        vec![
+            weight_parameter(percent.weight),
+            Parameter::new(
+                "wholes",
+                "Range of the number the percentage is taken of",
+                ParameterType::Range,
+                range(&percent.wholes),
+            ),
+            Parameter::new(
+                "what_percent",
+                "Allow asking for the percentage itself",
+                ParameterType::Boolean,
+                json!(percent.what_percent),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.percent.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let profile = &self.0.percent;
+        let percent = rng.gen_range(1..20) * 5;
+        if profile.what_percent && rng.gen_bool(0.5) {
+            // The whole is a multiple of 100 / gcd(percent, 100), so the part is an integer
+            let step = 100 / gcd(percent, 100).expect("The percent is between 5 and 95");
+            let (start, end) = (*profile.wholes.start(), *profile.wholes.end());
+            let multiples = (start.max(1) + step - 1) / step..=end / step;
+            let whole = if multiples.is_empty() {
+                step
+            } else {
+                rng.gen_range(multiples) * step
+            };
+            (
+                QuestionKind::WhatPercent {
+                    part: percent * whole / 100,
+                    whole,
+                },
+                Answer::Decimal(Decimal::from(percent)),
+            )
+        } else {
+            let whole = rng.gen_range(profile.wholes.clone());
+            let answer = Rational::new(percent * whole, 100)
+                .and_then(Decimal::from_rational)
+                .expect("Hundredths are decimals");
+            (
+                QuestionKind::PercentOf { percent, whole },
+                Answer::Decimal(answer),
+            )
+        }
+    }
+}
+
+/// Ratios to reduce to lowest terms, e.g. `simplify 12:18`.
+struct RatioGenerator(DifficultyProfile);
+
+impl QuestionGenerator for RatioGenerator {
+    fn name(&self) -> &'static str {
+        "ratio"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let ratio = &self.0.ratio;
+        vec![
+            weight_parameter(ratio.weight),
+            Parameter::new(
+                "terms",
+                "Range of the terms of the simplified ratio",
+                ParameterType::Range,
+                range(&ratio.terms),
+            ),
+            Parameter::new(
+                "max_factor",
+                "Largest common factor of the terms of the question",
+                ParameterType::Integer,
+                json!(ratio.max_factor),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.ratio.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let ratio = &self.0.ratio;
+        let antecedent = rng.gen_range(ratio.terms.clone());
+        // Settle for 1 if no other coprime term is in the range
+        let coprime: Vec<i64> = ratio
+            .terms
+            .clone()
+            .filter(|n| *n != antecedent && gcd(*n, antecedent) == Some(1))
+            .collect();
+        let consequent = coprime.choose(rng).copied().unwrap_or(1);
+        let factor = rng.gen_range(2..=ratio.max_factor.max(2));
+        (
+            QuestionKind::SimplifyRatio {
+                antecedent: antecedent * factor,
+                consequent: consequent * factor,
+            },
+            Answer::Ratio(antecedent, consequent),
+        )
+    }
+}
+
+/// Rounding to a power of ten, e.g. `round 4,587 to the nearest hundred`.
+struct RoundingGenerator(DifficultyProfile);
+
+impl QuestionGenerator for RoundingGenerator {
+    fn name(&self) -> &'static str {
+        "rounding"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let rounding = &self.0.rounding;
+        vec![
+            weight_parameter(rounding.weight),
+            Parameter::new(
+                "numbers",
+                "Range of the numbers to round",
+                ParameterType::Range,
+                range(&rounding.numbers),
+            ),
+            Parameter::new(
+                "places",
+                "Range of the exponents of the place to round to, 1 for tens",
+                ParameterType::Range,
+                range(&rounding.places),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.rounding.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let rounding = &self.0.rounding;
+        let (min, max) = (
+            *RoundingProfile::PLACES.start(),
+            *RoundingProfile::PLACES.end(),
+        );
+        let place = 10i64.pow(rng.gen_range(rounding.places.clone()).clamp(min, max));
+        // Rounding a number below the place gives 0 or the place itself, skip them
+        let (start, end) = (*rounding.numbers.start(), *rounding.numbers.end());
+        let number = rng.gen_range(start.max(place).min(end)..=end);
+        (
+            QuestionKind::Round { number, place },
+            Answer::Integer(round_to(number, place)),
+        )
+    }
+}
+
+/// Estimating sums and products by rounding the operands, e.g. `estimate 398 x 21`.
+struct EstimationGenerator(DifficultyProfile);
+
+impl QuestionGenerator for EstimationGenerator {
+    fn name(&self) -> &'static str {
+        "estimation"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let estimation = &self.0.estimation;
+        vec![
+            weight_parameter(estimation.weight),
+            Parameter::new(
+                "operands",
+                "Range of the operands",
+                ParameterType::Range,
+                range(&estimation.operands),
+            ),
+            Parameter::new(
+                "tolerance",
+                "Accepted distance from the expected answer in percent",
+                ParameterType::Integer,
+                json!(estimation.tolerance),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.estimation.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let operands = &self.0.estimation.operands;
+        let op = *[Operator::Add, Operator::Mul].choose(rng).unwrap();
+        let (a, b) = (
+            rng.gen_range(operands.clone()),
+            rng.gen_range(operands.clone()),
+        );
+        let estimate = match op {
+            Operator::Add => round_to_leading_digit(a) + round_to_leading_digit(b),
+            _ => round_to_leading_digit(a) * round_to_leading_digit(b),
+        };
+        (
+            QuestionKind::Estimate(Expr::binary(op, Expr::num(a), Expr::num(b))),
+            Answer::Integer(estimate),
+        )
+    }
+
+    /// Accepts answers within the tolerance of the estimate, and always the ones
+    /// between the estimate and the exact value.
+    fn grading(&self, kind: &QuestionKind, answer: &Answer) -> Grading {
+        let (QuestionKind::Estimate(expression), Answer::Integer(estimate)) = (kind, answer) else {
+            return Grading::Exact;
+        };
+        let tolerance = estimate.abs() * i64::from(self.0.estimation.tolerance) / 100;
+        let difference = expression
+            .eval()
+            .and_then(|exact| exact.to_integer())
+            .map(|exact| (exact - estimate).abs())
+            .unwrap_or(0);
+        Grading::Tolerance(Rational::from(tolerance.max(difference)))
+    }
+}
+
+/// Divisors, multiples and primes, e.g. `GCD of 12 and 18` or `factor 84 into primes`.
+struct NumberTheoryGenerator(DifficultyProfile);
+
+impl QuestionGenerator for NumberTheoryGenerator {
+    fn name(&self) -> &'static str {
+        "number_theory"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let number_theory = &self.0.number_theory;
+        vec![
+            weight_parameter(number_theory.weight),
+            Parameter::new(
+                "numbers",
+                "Range of the numbers in the questions",
+                ParameterType::Range,
+                range(&number_theory.numbers),
+            ),
+            Parameter::new(
+                "max_lcm",
+                "Largest least common multiple to ask for",
+                ParameterType::Integer,
+                json!(number_theory.max_lcm),
+            ),
+            Parameter::new(
+                "gcd",
+                "Ask for greatest common divisors",
+                ParameterType::Boolean,
+                json!(number_theory.gcd),
+            ),
+            Parameter::new(
+                "lcm",
+                "Ask for least common multiples",
+                ParameterType::Boolean,
+                json!(number_theory.lcm),
+            ),
+            Parameter::new(
+                "primes",
+                "Ask whether numbers are prime",
+                ParameterType::Boolean,
+                json!(number_theory.primes),
+            ),
+            Parameter::new(
+                "factorization",
+                "Ask for prime factorizations",
+                ParameterType::Boolean,
+                json!(number_theory.factorization),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.number_theory.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let profile = &self.0.number_theory;
+        let numbers = (*profile.numbers.start()).max(2)..=(*profile.numbers.end()).max(4);
+        let enabled = [
+            profile.gcd,
+            profile.lcm,
+            profile.primes,
+            profile.factorization,
+        ];
+        let index = WeightedIndex::new(enabled.map(u32::from))
+            .map(|distribution| distribution.sample(rng))
+            .unwrap_or(0);
+        // Half of the is-prime questions ask about a prime
+        let prime = rng.gen_bool(0.5);
+        // Draw until the numbers fit, settle for a fixed question if none do
+        let mut draw = |accept: &dyn Fn(i64, i64) -> bool| {
+            (0..100)
+                .map(|_| {
+                    (
+                        rng.gen_range(numbers.clone()),
+                        rng.gen_range(numbers.clone()),
+                    )
+                })
+                .find(|(a, b)| accept(*a, *b))
+        };
+        match index {
+            0 => {
+                let (a, b) = draw(&|a, b| a != b && gcd(a, b) > Some(1)).unwrap_or((12, 18));
+                let divisor = gcd(a, b).expect("The numbers have a common divisor");
+                (QuestionKind::Gcd { a, b }, Answer::Integer(divisor))
+            }
+            1 => {
+                let max_lcm = profile.max_lcm;
+                let (a, b) = draw(&|a, b| a != b && lcm(a, b).is_some_and(|m| m <= max_lcm))
+                    .unwrap_or((4, 6));
+                let multiple = lcm(a, b).expect("The numbers have a common multiple");
+                (QuestionKind::Lcm { a, b }, Answer::Integer(multiple))
+            }
+            2 => {
+                let n = draw(&|a, _| is_prime(a) == prime).map_or(91, |(a, _)| a);
+                (QuestionKind::IsPrime(n), Answer::Boolean(is_prime(n)))
+            }
+            _ => {
+                let n = draw(&|a, _| !is_prime(a)).map_or(84, |(a, _)| a);
+                (
+                    QuestionKind::Factorize(n),
+                    Answer::IntegerList(prime_factors(n)),
+                )
+            }
+        }
+    }
+
+    /// Prime factors are accepted in any order.
+    fn grading(&self, kind: &QuestionKind, _answer: &Answer) -> Grading {
+        match kind {
+            QuestionKind::Factorize(_) => Grading::Unordered,
+            _ => Grading::Exact,
+        }
+    }
+}
+
+/// Linear equations with an integer solution, e.g. `3x + 7 = 25` or `2(x - 4) = 10`.
+struct EquationGenerator(DifficultyProfile);
+
+impl QuestionGenerator for EquationGenerator {
+    fn name(&self) -> &'static str {
+        "equation"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let equation = &self.0.equation;
+        vec![
+            weight_parameter(equation.weight),
+            Parameter::new(
+                "solutions",
+                "Range of the solutions",
+                ParameterType::Range,
+                range(&equation.solutions),
+            ),
+            Parameter::new(
+                "coefficients",
+                "Range of the coefficients of x",
+                ParameterType::Range,
+                range(&equation.coefficients),
+            ),
+            Parameter::new(
+                "constants",
+                "Range of the constants added to or subtracted from x",
+                ParameterType::Range,
+                range(&equation.constants),
+            ),
+            Parameter::new(
+                "brackets",
+                "Allow equations like 2(x - 4) = 10",
+                ParameterType::Boolean,
+                json!(equation.brackets),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.equation.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let equation = &self.0.equation;
+        let x = rng.gen_range(equation.solutions.clone());
+        let a = rng.gen_range(equation.coefficients.clone());
+        let b = rng.gen_range(equation.constants.clone());
+        let brackets = equation.brackets && rng.gen_bool(0.5);
+        // Subtract only if the right side stays positive
+        let op = if rng.gen_bool(0.5) && (if brackets { x } else { a * x }) > b {
+            Operator::Sub
+        } else {
+            Operator::Add
+        };
+        let lhs = if brackets {
+            // a(x ± b)
+            Expr::binary(
+                Operator::Mul,
+                Expr::num(a),
+                Expr::binary(op, Expr::var('x'), Expr::num(b)),
+            )
+        } else {
+            // ax ± b
+            Expr::binary(
+                op,
+                Expr::binary(Operator::Mul, Expr::num(a), Expr::var('x')),
+                Expr::num(b),
+            )
+        };
+        let (coefficient, constant) = lhs.linear().expect("The left side is linear");
+        let rhs = coefficient
+            .checked_mul(Rational::from(x))
+            .and_then(|ax| ax.checked_add(constant))
+            .and_then(|value| value.to_integer())
+            .expect("Generated equations have an integer right side");
+        (
+            QuestionKind::Equation {
+                lhs,
+                rhs: Expr::num(rhs),
+            },
+            Answer::Integer(x),
+        )
+    }
+}
+
+/// Arithmetic and geometric sequences to continue, e.g. `3, 7, 11, 15, ?` or `2, 6, 18, ?`.
+struct SequenceGenerator(DifficultyProfile);
+
+impl QuestionGenerator for SequenceGenerator {
+    fn name(&self) -> &'static str {
+        "sequence"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let sequence = &self.0.sequence;
+        vec![
+            weight_parameter(sequence.weight),
+            Parameter::new(
+                "terms",
+                "Range of the number of terms shown",
+                ParameterType::Range,
+                range(&sequence.terms),
+            ),
+            Parameter::new(
+                "starts",
+                "Range of the first term of an increasing sequence",
+                ParameterType::Range,
+                range(&sequence.starts),
+            ),
+            Parameter::new(
+                "steps",
+                "Range of the differences of arithmetic sequences",
+                ParameterType::Range,
+                range(&sequence.steps),
+            ),
+            Parameter::new(
+                "geometric",
+                "Allow geometric sequences",
+                ParameterType::Boolean,
+                json!(sequence.geometric),
+            ),
+            Parameter::new(
+                "ratios",
+                "Range of the ratios of geometric sequences",
+                ParameterType::Range,
+                range(&sequence.ratios),
+            ),
+            Parameter::new(
+                "decreasing",
+                "Allow sequences counting down",
+                ParameterType::Boolean,
+                json!(sequence.decreasing),
+            ),
+            Parameter::new(
+                "max_value",
+                "Largest term of a geometric sequence",
+                ParameterType::Integer,
+                json!(sequence.max_value),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.sequence.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let sequence = &self.0.sequence;
+        let count = rng.gen_range(sequence.terms.clone()).max(3);
+        let terms: Option<Vec<i64>> = if sequence.geometric && rng.gen_bool(0.5) {
+            let ratio = rng.gen_range(sequence.ratios.clone()).max(2);
+            // Keep the last term within the limit, but start at 1 at least
+            let largest_start = ratio
+                .checked_pow(count as u32)
+                .map_or(1, |growth| (sequence.max_value / growth).max(1));
+            let end = (*sequence.starts.end()).clamp(1, largest_start);
+            let start = rng.gen_range((*sequence.starts.start()).clamp(1, end)..=end);
+            (0..=count as u32)
+                .map(|i| ratio.checked_pow(i)?.checked_mul(start))
+                .collect()
+        } else {
+            let step = rng.gen_range(sequence.steps.clone());
+            let start = rng.gen_range(sequence.starts.clone());
+            (0..=count as i64)
+                .map(|i| i.checked_mul(step)?.checked_add(start))
+                .collect()
+        };
+        // Settle for a fixed sequence if the terms overflow, validated profiles never do
+        let mut terms = terms.unwrap_or_else(|| vec![2, 4, 6, 8, 10]);
+        // A decreasing sequence is an increasing one backwards, so it stays above 0
+        if sequence.decreasing && rng.gen_bool(0.5) {
+            terms.reverse();
+        }
+        let answer = terms.pop().expect("Sequences have at least 4 terms");
+        (QuestionKind::Sequence { terms }, Answer::Integer(answer))
+    }
+}
+
+/// Picking the relation between two expressions, e.g. `38 + 47 ☐ 9 x 9`.
+struct ComparisonGenerator(DifficultyProfile);
+
+impl QuestionGenerator for ComparisonGenerator {
+    fn name(&self) -> &'static str {
+        "comparison"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let comparison = &self.0.comparison;
+        vec![
+            weight_parameter(comparison.weight),
+            Parameter::new(
+                "operands",
+                "Range the operands of additions and subtractions are drawn from",
+                ParameterType::Range,
+                range(&comparison.operands),
+            ),
+            Parameter::new(
+                "multiply",
+                "Allow products of one digit numbers",
+                ParameterType::Boolean,
+                json!(comparison.multiply),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.comparison.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let comparison = &self.0.comparison;
+        let (a, b) = (
+            rng.gen_range(comparison.operands.clone()),
+            rng.gen_range(comparison.operands.clone()),
+        );
+        let lhs = if comparison.multiply && rng.gen_bool(0.3) {
+            Expr::binary(
+                Operator::Mul,
+                Expr::num(rng.gen_range(2..=9)),
+                Expr::num(rng.gen_range(2..=9)),
+            )
+        } else if rng.gen_bool(0.5) {
+            Expr::binary(Operator::Add, Expr::num(a), Expr::num(b))
+        } else {
+            Expr::binary(Operator::Sub, Expr::num(a.max(b)), Expr::num(a.min(b)))
+        };
+        let value = lhs
+            .eval()
+            .and_then(|value| value.to_integer())
+            .expect("Comparisons have integer values");
+        // Close values make the question worth working out, a third of them are equal
+        let distance = rng.gen_range(1..=(value / 10).max(1));
+        let target = match rng.gen_range(0..3) {
+            0 => value - distance,
+            1 => value,
+            _ => value + distance,
+        }
+        .max(0);
+        let rhs = comparison_side(comparison.multiply, target, rng);
+        (
+            QuestionKind::Compare { lhs, rhs },
+            Answer::Relation(value.cmp(&target).into()),
+        )
+    }
+}
+
+/// An expression with the given value, a sum, a difference, a product or the number itself.
+fn comparison_side<R: Rng + ?Sized>(multiply: bool, value: i64, rng: &mut R) -> Expr {
+    let factor = (2..=9).find(|d| value % d == 0 && value / d <= 9 && value / d >= 2);
+    match (rng.gen_range(0..4), factor) {
+        (0, _) => Expr::num(value),
+        (1, Some(d)) if multiply => Expr::binary(Operator::Mul, Expr::num(d), Expr::num(value / d)),
+        (1 | 2, _) if value >= 2 => {
+            let a = rng.gen_range(1..value);
+            Expr::binary(Operator::Add, Expr::num(a), Expr::num(value - a))
+        }
+        _ => {
+            let b = rng.gen_range(1..=value.max(10));
+            Expr::binary(Operator::Sub, Expr::num(value + b), Expr::num(b))
+        }
+    }
+}
+
+/// `None` on overflow or if both numbers are 0.
+fn lcm(a: i64, b: i64) -> Option<i64> {
+    a.checked_div(gcd(a, b)?)?.checked_mul(b)
+}
+
+pub(crate) fn is_prime(n: i64) -> bool {
+    n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| n % d != 0)
+}
+
+/// Prime factors in ascending order, e.g. `[2, 2, 3, 7]` for 84.
+fn prime_factors(mut n: i64) -> Vec<i64> {
+    let mut factors = vec![];
+    let mut d = 2;
+    while d * d <= n {
+        while n % d == 0 {
+            factors.push(d);
+            n /= d;
+        }
+        d += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
+
+/// Rounds half up to a multiple of `place`, e.g. 4,550 to 4,600.
+fn round_to(number: i64, place: i64) -> i64 {
+    (number + place / 2).div_euclid(place) * place
+}
+
+/// Rounds to one significant digit, e.g. 398 to 400 and 21 to 20.
+fn round_to_leading_digit(number: i64) -> i64 {
+    let place = 10i64.pow(number.unsigned_abs().max(1).ilog10());
+    round_to(number, place)
+}
+
+/// Wraps an expression with integer operands together with its answer.
+fn integer_question(expression: Expr) -> (QuestionKind, Answer) {
+    let answer = expression
+        .eval()
+        .and_then(|value| value.to_integer())
+        .expect("Generated questions always have an integer answer");
+    (QuestionKind::Evaluate(expression), Answer::Integer(answer))
+}
+
+fn generate_times_table<R: Rng + ?Sized>(profile: &DifficultyProfile, rng: &mut R) -> Expr {
+    let table = profile.times_table.tables.choose(rng).copied().unwrap_or(2);
+    let factor = rng.gen_range(2..=profile.times_table.max_factor.max(2));
+    if profile.times_table.divide && table != 0 && rng.gen_bool(0.5) {
+        Expr::binary(Operator::Div, Expr::num(table * factor), Expr::num(table))
+    } else if rng.gen_bool(0.5) {
+        Expr::binary(Operator::Mul, Expr::num(table), Expr::num(factor))
+    } else {
+        Expr::binary(Operator::Mul, Expr::num(factor), Expr::num(table))
+    }
+}
+
+/// A single operation with integer result, `op` is the index of
+/// addition, subtraction, multiplication or division.
+fn generate_operation<R: Rng + ?Sized>(
+    profile: &DifficultyProfile,
+    op: usize,
+    rng: &mut R,
+) -> Expr {
+    let constraint = match op {
+        0 => profile.carries,
+        1 => profile.borrows,
+        _ => None,
+    };
+    let mut expr = generate_operands(profile, op, rng);
+    // Retry until the number of carries or borrows fits,
+    // give up if the operand range doesn't allow it
+    if let Some(constraint) = constraint {
+        for _ in 0..1000 {
+            if let Some((_, count)) = expr.regroupings() {
+                if constraint.matches(count) {
+                    break;
+                }
+            }
+            expr = generate_operands(profile, op, rng);
+        }
+    }
+    expr
+}
+
+fn generate_operands<R: Rng + ?Sized>(profile: &DifficultyProfile, op: usize, rng: &mut R) -> Expr {
+    let negative = profile.negative_numbers;
+    match op {
+        0 => {
+            // Addition
+            let a = rng.gen_range(profile.addition.operands.clone());
+            let b = rng.gen_range(profile.addition.operands.clone());
+            Expr::binary(
+                Operator::Add,
+                Expr::num(random_sign(a, negative, rng)),
+                Expr::num(random_sign(b, negative, rng)),
+            )
+        }
+        1 => {
+            // Subtraction, ensure result is non-negative unless negative numbers are enabled
+            let a = rng.gen_range(profile.subtraction.operands.clone());
+            let b = rng.gen_range(profile.subtraction.operands.clone());
+            let (a, b) = if a >= b || negative { (a, b) } else { (b, a) };
+            Expr::binary(
+                Operator::Sub,
+                Expr::num(random_sign(a, negative, rng)),
+                Expr::num(random_sign(b, negative, rng)),
+            )
+        }
+        2 => {
+            // Multiplication
+            let a = rng.gen_range(profile.multiplication.operands.clone());
+            let b = rng.gen_range(profile.multiplication.operands.clone());
+            Expr::binary(
+                Operator::Mul,
+                Expr::num(random_sign(a, negative, rng)),
+                Expr::num(random_sign(b, negative, rng)),
+            )
+        }
+        3 => {
+            // Division, ensure result is an integer and the divisor isn't 0
+            let divisor = match rng.gen_range(profile.division.operands.clone()) {
+                0 => 1,
+                divisor => random_sign(divisor, negative, rng),
+            };
+            let quotient = random_sign(
+                rng.gen_range(profile.division.operands.clone()),
+                negative,
+                rng,
+            );
+            let dividend = divisor * quotient;
+            Expr::binary(Operator::Div, Expr::num(dividend), Expr::num(divisor))
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Flips the sign of `n` with a chance of one half if `negative` is set.
+fn random_sign<R: Rng + ?Sized>(n: i64, negative: bool, rng: &mut R) -> i64 {
+    if negative && rng.gen_bool(0.5) {
+        -n
+    } else {
+        n
+    }
+}
+
+fn generate_multi_step<R: Rng + ?Sized>(profile: &DifficultyProfile, rng: &mut R) -> Expr {
+    let terms = rng.gen_range(profile.multi_step.terms.clone()).max(2);
+    for _ in 0..1000 {
+        if let Some((expr, _)) = generate_subexpression(profile, terms, rng) {
+            return expr;
+        }
+    }
+    // The profile leaves no room for valid expressions, settle for an addition
+    let operands = profile.multi_step.operands.clone();
+    Expr::binary(
+        Operator::Add,
+        Expr::num(rng.gen_range(operands.clone())),
+        Expr::num(rng.gen_range(operands)),
+    )
+}
+
+/// Builds a random tree with the given number of operands, together with its value.
+/// Returns `None` if an intermediate result would be negative, inexact or too large,
+/// the caller starts over in that case.
+fn generate_subexpression<R: Rng + ?Sized>(
+    profile: &DifficultyProfile,
+    terms: usize,
+    rng: &mut R,
+) -> Option<(Expr, i64)> {
+    let operands = profile.multi_step.operands.clone();
+    if terms == 1 {
+        let n = rng.gen_range(operands);
+        return Some((Expr::num(n), n));
+    }
+
+    let weights = [
+        profile.addition.weight,
+        profile.subtraction.weight,
+        profile.multiplication.weight,
+        profile.division.weight,
+    ];
+    let op = match WeightedIndex::new(weights).map(|distribution| distribution.sample(rng)) {
+        Ok(1) => Operator::Sub,
+        Ok(2) => Operator::Mul,
+        Ok(3) => Operator::Div,
+        _ => Operator::Add,
+    };
+    let left_terms = rng.gen_range(1..terms);
+    let (lhs, a) = generate_subexpression(profile, left_terms, rng)?;
+    let (rhs, b) = match (op, terms - left_terms) {
+        (Operator::Sub, 1) => {
+            // Keep the difference non-negative
+            let upper = a.min(*operands.end());
+            if upper < *operands.start() {
+                return None;
+            }
+            let n = rng.gen_range(*operands.start()..=upper);
+            (Expr::num(n), n)
+        }
+        (Operator::Div, 1) => {
+            // Pick one of the divisors so the quotient is an integer
+            let divisors: Vec<i64> = operands
+                .clone()
+                .filter(|d| *d > 1 && *d < a && a % d == 0)
+                .collect();
+            if divisors.is_empty() {
+                return None;
+            }
+            let n = divisors[rng.gen_range(0..divisors.len())];
+            (Expr::num(n), n)
+        }
+        (_, right_terms) => generate_subexpression(profile, right_terms, rng)?,
+    };
+    let expr = Expr::binary(op, lhs, rhs);
+    let value = expr.eval()?.to_integer()?;
+    if value < 0 || value > profile.multi_step.max_value || (op == Operator::Div && b <= 1) {
+        return None;
+    }
+    Some((expr, value))
+}
+
+fn generate_fraction<R: Rng + ?Sized>(
+    profile: &DifficultyProfile,
+    rng: &mut R,
+) -> (QuestionKind, Answer) {
+    let fraction = &profile.fraction;
+    let op = if fraction.multiply_divide {
+        [Operator::Add, Operator::Sub, Operator::Mul, Operator::Div][rng.gen_range(0..4)]
+    } else {
+        [Operator::Add, Operator::Sub][rng.gen_range(0..2)]
+    };
+    let mut draw = || {
+        let a_denom = rng.gen_range(fraction.denominators.clone());
+        let b_denom = if fraction.unlike_denominators && rng.gen_bool(0.5) {
+            rng.gen_range(fraction.denominators.clone())
+        } else {
+            a_denom
+        };
+        (proper_fraction(a_denom, rng), proper_fraction(b_denom, rng))
+    };
+    // `2/3 - 2/3` is trivial, draw again unless the denominators allow no other fractions
+    let (a, b) = (0..100)
+        .map(|_| draw())
+        .find(|(a, b)| op != Operator::Sub || a != b)
+        .unwrap_or_else(&mut draw);
+    // Ensure the result of subtraction is non-negative
+    let (a, b) = if op == Operator::Sub && a < b {
+        (b, a)
+    } else {
+        (a, b)
+    };
+    let expression = Expr::binary(op, Expr::frac(a), Expr::frac(b));
+    let answer = expression.eval().expect("Fraction operands never overflow");
+    (QuestionKind::Evaluate(expression), Answer::Fraction(answer))
+}
+
+/// A random proper fraction that is already in reduced form, so it keeps the denominator.
+/// The numerator is never 0, so it's safe to divide by it.
+fn proper_fraction<R: Rng + ?Sized>(denom: i64, rng: &mut R) -> Rational {
+    let numerators: Vec<i64> = (1..denom).filter(|n| gcd(*n, denom) == Some(1)).collect();
+    Rational::new(numerators[rng.gen_range(0..numerators.len())], denom).unwrap()
+}
+
+fn generate_decimal<R: Rng + ?Sized>(
+    profile: &DifficultyProfile,
+    rng: &mut R,
+) -> (QuestionKind, Answer) {
+    let decimal = &profile.decimal;
+    let op = if decimal.multiply {
+        [Operator::Add, Operator::Sub, Operator::Mul][rng.gen_range(0..3)]
+    } else {
+        [Operator::Add, Operator::Sub][rng.gen_range(0..2)]
+    };
+    let whole = if op == Operator::Mul {
+        *decimal.whole.start()..=*decimal.whole.end().min(&9)
+    } else {
+        decimal.whole.clone()
+    };
+    let mut operand = || {
+        let places = rng.gen_range(1..=decimal.places.max(1));
+        let unit = 10_i64.pow(places);
+        // The last digit is never 0, so the operand shows all of its places
+        let fraction = rng.gen_range(0..unit / 10) * 10 + rng.gen_range(1..10);
+        Decimal::new(rng.gen_range(whole.clone()) * unit + fraction, places)
+    };
+    let a = operand();
+    // `89.1 - 89.1` is trivial, draw again
+    let b = (0..100)
+        .map(|_| operand())
+        .find(|b| op != Operator::Sub || *b != a)
+        .unwrap_or(a);
+    // Ensure the result of subtraction is non-negative
+    let (a, b) = if op == Operator::Sub && a.to_rational() < b.to_rational() {
+        (b, a)
+    } else {
+        (a, b)
+    };
+    let expression = Expr::binary(op, Expr::dec(a), Expr::dec(b));
+    let answer = expression
+        .eval()
+        .and_then(Decimal::from_rational)
+        .expect("Sums and products of decimals are decimals");
+    (QuestionKind::Evaluate(expression), Answer::Decimal(answer))
+}
+
+fn generate_power<R: Rng + ?Sized>(profile: &DifficultyProfile, rng: &mut R) -> Expr {
+    let power = &profile.power;
+    // 1 is a power, 2 a square root and 3 a cube root
+    let mut kinds = vec![1];
+    if power.square_roots {
+        kinds.push(2);
+    }
+    if power.cube_roots {
+        kinds.push(3);
+    }
+    let kind = kinds[rng.gen_range(0..kinds.len())];
+    let exponent = if kind == 1 {
+        rng.gen_range(2..=power.max_exponent.max(2))
+    } else {
+        kind
+    };
+    // Retry until the power is small enough, settle for the smallest base otherwise
+    // and for `2²` if even that's too large, validated profiles never need to
+    let fits = |base: i64| {
+        base.checked_pow(exponent)
+            .is_some_and(|value| value <= power.max_value)
+    };
+    let (base, exponent) = (0..100)
+        .map(|_| rng.gen_range(power.bases.clone()))
+        .chain([*power.bases.start()])
+        .find(|base| fits(*base))
+        .map_or((2, 2), |base| (base, exponent));
+    let value = base
+        .checked_pow(exponent)
+        .expect("The power fits the largest value");
+    match kind {
+        1 => Expr::pow(Expr::num(base), exponent),
+        _ => Expr::root(Expr::num(value), exponent),
+    }
+}
+
+/// Word problems filled in from the templates of the profile, in its locale.
+struct WordProblemGenerator(DifficultyProfile);
+
+impl QuestionGenerator for WordProblemGenerator {
+    fn name(&self) -> &'static str {
+        "word_problem"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let word_problem = &self.0.word_problem;
+        vec![
+            weight_parameter(self.weight()),
+            Parameter::new(
+                "templates",
+                "Names of the templates to pick from",
+                ParameterType::TextList,
+                json!(word_problem
+                    .templates
+                    .iter()
+                    .map(|template| &template.name)
+                    .collect::<Vec<_>>()),
+            ),
+            Parameter::new(
+                "locale",
+                "Language of the text, templates without it fall back to English",
+                ParameterType::Text,
+                json!(word_problem.locale),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        if self.0.word_problem.templates.is_empty() {
+            0
+        } else {
+            self.0.word_problem.weight
+        }
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let word_problem = &self.0.word_problem;
+        let templates = &word_problem.templates;
+        // Starting at a random template, moving on when its constraints are missed
+        let start = rng.gen_range(0..templates.len());
+        let problem = templates
+            .iter()
+            .cycle()
+            .skip(start)
+            .take(templates.len())
+            .find_map(|template| {
+                template
+                    .generate(&word_problem.locale, rng)
+                    .map_err(|e| log::warn!("{:#}", e))
+                    .ok()
+                    .map(|(text, answer)| (template.name.clone(), text, answer))
+            });
+        // Settle for a fixed question if none of them work out
+        let (template, text, answer) = problem.unwrap_or_else(|| {
+            let text = match word_problem.locale.as_str() {
+                "zh" => "小红有12个苹果，又买了7个。现在一共有多少个苹果？",
+                _ => "Mia has 12 apples and buys 7 more. How many apples are there now?",
+            };
+            ("buy_more".to_string(), text.to_string(), 19)
+        });
+        (
+            QuestionKind::WordProblem { template, text },
+            Answer::Integer(answer),
+        )
+    }
+}
+
+/// Making change, totaling a shopping list or comparing unit prices, e.g.
+/// `change from ¥20.00 for ¥12.50`.
+struct MoneyGenerator(DifficultyProfile);
+
+impl QuestionGenerator for MoneyGenerator {
+    fn name(&self) -> &'static str {
+        "money"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let money = &self.0.money;
+        vec![
+            weight_parameter(money.weight),
+            Parameter::new(
+                "currency",
+                "Currency the amounts are shown in",
+                ParameterType::Choice(&["cny", "usd"]),
+                json!(money.currency.name()),
+            ),
+            Parameter::new(
+                "prices",
+                "Range of the prices in minor units, e.g. fen or cents",
+                ParameterType::Range,
+                range(&money.prices),
+            ),
+            Parameter::new(
+                "step",
+                "Prices are multiples of this many minor units",
+                ParameterType::Integer,
+                json!(money.step),
+            ),
+            Parameter::new(
+                "items",
+                "Range of the number of items on a shopping list",
+                ParameterType::Range,
+                range(&money.items),
+            ),
+            Parameter::new(
+                "unit_prices",
+                "Allow comparing prices per item",
+                ParameterType::Boolean,
+                json!(money.unit_prices),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.money.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let money = &self.0.money;
+        let amount = |minor| Money::new(minor, money.currency);
+        match rng.gen_range(0..if money.unit_prices { 3 } else { 2 }) {
+            0 => {
+                let price = draw_price(&money.prices, money.step, rng);
+                let paid = note_above(price, rng.gen_bool(0.3));
+                (
+                    QuestionKind::MakeChange {
+                        price: amount(price),
+                        paid: amount(paid),
+                    },
+                    Answer::Money(amount(paid - price)),
+                )
+            }
+            1 => {
+                let items = (0..rng.gen_range(money.items.clone()))
+                    .map(|_| {
+                        (
+                            rng.gen_range(1..=3),
+                            draw_price(&money.prices, money.step, rng),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let total = items.iter().map(|(quantity, price)| quantity * price).sum();
+                (
+                    QuestionKind::ShoppingTotal {
+                        items: items
+                            .into_iter()
+                            .map(|(quantity, price)| (quantity, amount(price)))
+                            .collect(),
+                    },
+                    Answer::Money(amount(total)),
+                )
+            }
+            _ => {
+                let lhs_count = rng.gen_range(2..=6);
+                let rhs_count = loop {
+                    let count = rng.gen_range(2..=6);
+                    if count != lhs_count {
+                        break count;
+                    }
+                };
+                // The same price per item on the right, a few steps off two times out of three
+                let unit = draw_price(&money.unit_price_range(), money.step, rng);
+                let offset = money.step * rng.gen_range(1..=rhs_count);
+                let lhs = lhs_count * unit;
+                let rhs = match rng.gen_range(0..3) {
+                    0 if rhs_count * unit > offset => rhs_count * unit - offset,
+                    1 => rhs_count * unit,
+                    _ => rhs_count * unit + offset,
+                };
+                (
+                    QuestionKind::CompareUnitPrices {
+                        lhs: (lhs_count, amount(lhs)),
+                        rhs: (rhs_count, amount(rhs)),
+                    },
+                    Answer::Relation((lhs * rhs_count).cmp(&(rhs * lhs_count)).into()),
+                )
+            }
+        }
+    }
+}
+
+/// A price in minor units from the range, a multiple of the step.
+/// Validated profiles have a multiple of the step in their price ranges.
+fn draw_price<R: Rng + ?Sized>(prices: &RangeInclusive<i64>, step: i64, rng: &mut R) -> i64 {
+    let step = step.max(1);
+    let (start, end) = (prices.start(), prices.end());
+    rng.gen_range((start + step - 1) / step..=end / step) * step
+}
+
+/// The smallest bank note above the price in minor units, or the one after it,
+/// e.g. `¥20.00` or `¥50.00` for `¥12.50`. Larger prices are paid in hundreds.
+fn note_above(price: i64, next: bool) -> i64 {
+    let notes = [100, 500, 1000, 2000, 5000, 10000];
+    let index = notes.iter().position(|&note| note > price);
+    match index.map(|index| index + usize::from(next)) {
+        Some(index) if index < notes.len() => notes[index],
+        _ => (price / 10000 + 1 + i64::from(next)) * 10000,
+    }
+}
+
+/// Clock arithmetic in whole steps of minutes, times stay between 0:00 and 23:59.
+struct TimeGenerator(DifficultyProfile);
+
+impl QuestionGenerator for TimeGenerator {
+    fn name(&self) -> &'static str {
+        "time"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let time = &self.0.time;
+        vec![
+            weight_parameter(time.weight),
+            Parameter::new(
+                "step",
+                "Times and durations are multiples of this many minutes",
+                ParameterType::Integer,
+                json!(time.step),
+            ),
+            Parameter::new(
+                "max_duration",
+                "Longest duration in minutes",
+                ParameterType::Integer,
+                json!(time.max_duration),
+            ),
+            Parameter::new(
+                "before",
+                "Allow going back in time",
+                ParameterType::Boolean,
+                json!(time.before),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.time.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let time = &self.0.time;
+        let step = time.step.clamp(1, 60);
+        // Up to 12 hours, so the start fits in the day between 6:00 and 22:00
+        let max_duration = time.max_duration.clamp(step, 720);
+        let duration = rng.gen_range(1..=max_duration / step) * step;
+        let between = rng.gen_bool(0.5);
+        let before = !between && time.before && rng.gen_bool(0.5);
+        let start = if before {
+            rng.gen_range((duration.max(360) + step - 1) / step..=1320 / step) * step
+        } else {
+            rng.gen_range((360 + step - 1) / step..=1320.min(1439 - duration) / step) * step
+        };
+        if between {
+            return (
+                QuestionKind::MinutesBetween {
+                    start,
+                    end: start + duration,
+                },
+                Answer::Integer(duration),
+            );
+        }
+        let duration = if before { -duration } else { duration };
+        let end = start + duration;
+        (
+            QuestionKind::ClockTime { start, duration },
+            Answer::Time {
+                hour: end / 60,
+                minute: end % 60,
+            },
+        )
+    }
+
+    /// Times are accepted on the 12-hour clock too, e.g. `1:15` for `13:15` or `12:15`
+    /// for `0:15`.
+    fn grading(&self, _kind: &QuestionKind, answer: &Answer) -> Grading {
+        match *answer {
+            // Hours 0 and 13 to 23 are 12 and 1 to 11 on the 12-hour clock
+            Answer::Time { hour, minute } if hour == 0 || hour > 12 => {
+                Grading::AcceptedSet(vec![Answer::Time {
+                    hour: (hour + 11) % 12 + 1,
+                    minute,
+                }])
+            }
+            _ => Grading::Exact,
+        }
+    }
+}
+
+/// Conversions between a unit and a larger one of the same family and system,
+/// e.g. `3.2 km = ? m` or `4500 g = ? kg`.
+struct UnitGenerator(DifficultyProfile);
+
+impl QuestionGenerator for UnitGenerator {
+    fn name(&self) -> &'static str {
+        "unit"
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let unit = &self.0.unit;
+        vec![
+            weight_parameter(unit.weight),
+            Parameter::new(
+                "length",
+                "Allow units of length",
+                ParameterType::Boolean,
+                json!(unit.length),
+            ),
+            Parameter::new(
+                "mass",
+                "Allow units of mass",
+                ParameterType::Boolean,
+                json!(unit.mass),
+            ),
+            Parameter::new(
+                "volume",
+                "Allow units of volume",
+                ParameterType::Boolean,
+                json!(unit.volume),
+            ),
+            Parameter::new(
+                "decimals",
+                "Allow amounts with decimals, metric units only",
+                ParameterType::Boolean,
+                json!(unit.decimals),
+            ),
+            Parameter::new(
+                "imperial",
+                "Add imperial units besides the metric ones",
+                ParameterType::Boolean,
+                json!(unit.imperial),
+            ),
+        ]
+    }
+
+    fn weight(&self) -> u32 {
+        self.0.unit.weight
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer) {
+        let profile = &self.0.unit;
+        let mut families = vec![];
+        for (enabled, family) in [
+            (profile.length, UnitFamily::Length),
+            (profile.mass, UnitFamily::Mass),
+            (profile.volume, UnitFamily::Volume),
+        ] {
+            if enabled {
+                families.push(family);
+            }
+        }
+        let family = families.choose(rng).copied().unwrap_or(UnitFamily::Length);
+        // Pairs of a smaller and a larger unit, up to `mi` and `yd` apart
+        let pairs = Unit::ALL
+            .iter()
+            .flat_map(|small| Unit::ALL.iter().map(move |large| (*small, *large)))
+            .filter(|(small, large)| {
+                small.family() == family
+                    && large.family() == family
+                    && small.is_imperial() == large.is_imperial()
+                    && (profile.imperial || !small.is_imperial())
+                    && small.size() < large.size()
+                    && large.size() / small.size() <= 1760
+            })
+            .collect::<Vec<_>>();
+        let (small, large) = *pairs.choose(rng).expect("Every family has metric units");
+        let factor = large.size() / small.size();
+        // The amount in the larger unit, with no more decimals than the factor has zeros,
+        // so the amount in the smaller unit is an integer
+        let places = factor.ilog10().min(2);
+        let large_amount = if profile.decimals && !small.is_imperial() && rng.gen_bool(0.5) {
+            let places = rng.gen_range(1..=places);
+            Decimal::new(rng.gen_range(1..10 * 10i64.pow(places)), places)
+        } else {
+            Decimal::from(rng.gen_range(1..=20))
+        };
+        let small_amount = Decimal::from_rational(
+            large_amount
+                .to_rational()
+                .checked_mul(Rational::from(factor))
+                .expect("Small amounts don't overflow"),
+        )
+        .expect("Products with integers terminate");
+        let (value, from, to, result) = if rng.gen_bool(0.5) {
+            (large_amount, large, small, small_amount)
+        } else {
+            (small_amount, small, large, large_amount)
+        };
+        let answer = match result.to_rational().to_integer() {
+            Some(n) if !profile.decimals => Answer::Integer(n),
+            _ => Answer::Decimal(result),
+        };
+        (QuestionKind::ConvertUnits { value, from, to }, answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{expr::Notation, generator::question_rng};
+
+    /// Whether the terms and the answer go up or down by the same step or ratio.
+    fn is_sequence(kind: &QuestionKind, answer: &Answer) -> bool {
+        let (QuestionKind::Sequence { terms }, Answer::Integer(answer)) = (kind, answer) else {
+            return false;
+        };
+        let mut terms = terms.clone();
+        terms.push(*answer);
+        let pairs = || terms.windows(2).map(|pair| (pair[0], pair[1]));
+        let arithmetic = pairs().all(|(a, b)| b - a == terms[1] - terms[0]);
+        let geometric = |(a, b): (i64, i64)| {
+            let (small, large) = (a.min(b), a.max(b));
+            small > 0
+                && large % small == 0
+                && large / small == terms[0].max(terms[1]) / terms[0].min(terms[1])
+        };
+        arithmetic || pairs().all(geometric)
+    }
+
+    #[test]
+    fn preset_sequences() {
+        for grade in 1..=6 {
+            let generator = SequenceGenerator(DifficultyProfile::grade(grade));
+            let mut rng = question_rng(grade.into(), 0);
+            for _ in 0..1000 {
+                let (kind, answer) = generator.generate(&mut rng);
+                assert!(
+                    is_sequence(&kind, &answer),
+                    "{}",
+                    kind.render(Notation::Plain)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sequences_never_overflow() {
+        let mut profile = DifficultyProfile::grade(6);
+        profile.sequence.starts = -5..=0;
+        profile.sequence.steps = 1..=i64::MAX;
+        profile.sequence.ratios = 2..=i64::MAX;
+        profile.sequence.max_value = 0;
+        assert!(profile.validate().is_err());
+        let generator = SequenceGenerator(profile);
+        let mut rng = question_rng(0, 0);
+        for _ in 0..1000 {
+            let (kind, answer) = generator.generate(&mut rng);
+            assert!(
+                is_sequence(&kind, &answer),
+                "{}",
+                kind.render(Notation::Plain)
+            );
+        }
+    }
+
+    #[test]
+    fn powers_never_overflow() {
+        let mut profile = DifficultyProfile::grade(6);
+        profile.power.bases = 10..=i64::MAX;
+        profile.power.max_exponent = 40;
+        profile.power.max_value = 5;
+        assert!(profile.validate().is_err());
+        let mut rng = question_rng(0, 0);
+        for _ in 0..100 {
+            let expression = generate_power(&profile, &mut rng);
+            assert_eq!(
+                expression.render(Notation::Plain),
+                match expression {
+                    Expr::Pow { .. } => "2²",
+                    _ => "√4",
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn accepts_times_on_the_12_hour_clock() {
+        let generator = TimeGenerator(DifficultyProfile::grade(3));
+        let kind = QuestionKind::ClockTime {
+            start: 600,
+            duration: 15,
+        };
+        let time = |hour, minute| Answer::Time { hour, minute };
+        for (expected, accepted, rejected) in [
+            (time(0, 15), time(12, 15), time(1, 15)),
+            (time(12, 15), time(12, 15), time(0, 15)),
+            (time(13, 15), time(1, 15), time(11, 15)),
+            (time(23, 50), time(11, 50), time(10, 50)),
+            (time(9, 5), time(9, 5), time(21, 5)),
+        ] {
+            let grading = generator.grading(&kind, &expected);
+            assert!(grading.is_correct(&expected, &expected));
+            assert!(grading.is_correct(&expected, &accepted), "{:?}", expected);
+            assert!(!grading.is_correct(&expected, &rejected), "{:?}", expected);
+        }
+    }
+
+    /// The integer value of an expression, every generated operand has one.
+    fn integer(expression: &Expr) -> i64 {
+        expression
+            .eval()
+            .and_then(|value| value.to_integer())
+            .expect("Generated expressions have integer values")
+    }
+
+    #[test]
+    fn missing_operands_complete_the_equation() {
+        for grade in 1..=6 {
+            let profile = DifficultyProfile::grade(grade);
+            let generator = MissingOperandGenerator(profile.clone());
+            let mut rng = question_rng(grade.into(), 0);
+            for _ in 0..1000 {
+                let (kind, answer) = generator.generate(&mut rng);
+                let QuestionKind::MissingOperand {
+                    expression: expression @ Expr::BinOp { op, lhs, rhs },
+                    blank,
+                } = &kind
+                else {
+                    panic!("{:?}", kind);
+                };
+                let (a, b, result) = (integer(lhs), integer(rhs), integer(expression));
+                assert_eq!(answer, Answer::Integer([a, b][*blank]));
+                // The numbers drawn from the range of the operation
+                let (range, drawn) = match op {
+                    Operator::Add => (&profile.addition.operands, [a, b]),
+                    Operator::Sub => {
+                        assert!(result >= 0, "{:?}", kind);
+                        (&profile.subtraction.operands, [a, b])
+                    }
+                    Operator::Mul => (&profile.multiplication.operands, [a, b]),
+                    Operator::Div => (&profile.division.operands, [b, result]),
+                };
+                assert!(drawn.iter().all(|n| range.contains(n)), "{:?}", kind);
+            }
+        }
+    }
+
+    #[test]
+    fn number_theory_answers() {
+        let profile = DifficultyProfile::grade(5);
+        let number_theory = &profile.number_theory;
+        let numbers = &number_theory.numbers;
+        let generator = NumberTheoryGenerator(profile.clone());
+        let mut rng = question_rng(0, 0);
+        for _ in 0..2000 {
+            let (kind, answer) = generator.generate(&mut rng);
+            match (&kind, &answer) {
+                (QuestionKind::Gcd { a, b }, Answer::Integer(divisor)) => {
+                    assert!(a != b && numbers.contains(a) && numbers.contains(b));
+                    assert!(*divisor > 1 && a % divisor == 0 && b % divisor == 0);
+                    assert_eq!(gcd(a / divisor, b / divisor), Some(1), "{:?}", kind);
+                }
+                (QuestionKind::Lcm { a, b }, Answer::Integer(multiple)) => {
+                    assert!(a != b && numbers.contains(a) && numbers.contains(b));
+                    assert!(*multiple <= number_theory.max_lcm);
+                    assert!(multiple % a == 0 && multiple % b == 0);
+                    assert_eq!(gcd(multiple / a, multiple / b), Some(1), "{:?}", kind);
+                }
+                (QuestionKind::IsPrime(n), Answer::Boolean(prime)) => {
+                    assert!(numbers.contains(n));
+                    let divisors = (2..*n).filter(|d| n % d == 0).count();
+                    assert_eq!(*prime, divisors == 0, "{:?}", kind);
+                }
+                (QuestionKind::Factorize(n), Answer::IntegerList(factors)) => {
+                    assert!(numbers.contains(n) && factors.len() >= 2, "{:?}", kind);
+                    assert!(factors.windows(2).all(|pair| pair[0] <= pair[1]));
+                    assert!(factors.iter().all(|factor| is_prime(*factor)));
+                    assert_eq!(factors.iter().product::<i64>(), *n);
+                }
+                _ => panic!("{:?} = {:?}", kind, answer),
+            }
+        }
+    }
+
+    #[test]
+    fn equations_hold_for_the_solution() {
+        let profile = DifficultyProfile::grade(6);
+        let equation = &profile.equation;
+        assert!(equation.brackets);
+        let generator = EquationGenerator(profile.clone());
+        let mut rng = question_rng(0, 0);
+        for _ in 0..1000 {
+            let (kind, answer) = generator.generate(&mut rng);
+            let (QuestionKind::Equation { lhs, rhs }, Answer::Integer(x)) = (&kind, &answer) else {
+                panic!("{:?} = {:?}", kind, answer);
+            };
+            let (coefficient, constant) = lhs.linear().unwrap();
+            let value = coefficient
+                .checked_mul(Rational::from(*x))
+                .and_then(|ax| ax.checked_add(constant))
+                .and_then(|value| value.to_integer());
+            assert_eq!(value, Some(integer(rhs)), "{:?}", kind);
+            assert!(integer(rhs) > 0, "{:?}", kind);
+            assert!(equation.solutions.contains(x));
+            let coefficient = coefficient.to_integer().unwrap();
+            assert!(equation.coefficients.contains(&coefficient), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn conversions_keep_the_amount() {
+        let metric = DifficultyProfile::grade(4);
+        let mut imperial = DifficultyProfile::grade(4);
+        imperial.unit.decimals = false;
+        imperial.unit.imperial = true;
+        for profile in [metric, imperial] {
+            let unit = &profile.unit;
+            let generator = UnitGenerator(profile.clone());
+            let mut rng = question_rng(0, 0);
+            for _ in 0..1000 {
+                let (kind, answer) = generator.generate(&mut rng);
+                let QuestionKind::ConvertUnits { value, from, to } = &kind else {
+                    panic!("{:?}", kind);
+                };
+                assert_ne!(from, to);
+                assert_eq!(from.family(), to.family());
+                assert_eq!(from.is_imperial(), to.is_imperial());
+                assert!(unit.imperial || !from.is_imperial(), "{:?}", kind);
+                let amount = |value: Rational, unit: &Unit| {
+                    value.checked_mul(Rational::from(unit.size())).unwrap()
+                };
+                let result = answer.to_rational().unwrap();
+                assert!(result > Rational::from(0), "{:?}", kind);
+                assert_eq!(amount(value.to_rational(), from), amount(result, to));
+                if !unit.decimals {
+                    assert!(matches!(answer, Answer::Integer(_)), "{:?}", answer);
+                    assert!(value.to_rational().to_integer().is_some());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,562 @@
+use rand::{seq::SliceRandom, Rng};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    answer::{Answer, Relation},
+    builtin::is_prime,
+    decimal::Decimal,
+    expr::{Expr, Operator},
+    grading::Grading,
+    kind::QuestionKind,
+    money::Money,
+    rational::Rational,
+};
+
+/// A typical error a wrong choice is built from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Misconception {
+    /// Forgot to carry, e.g. `38 + 47 = 75`.
+    MissedCarry,
+    /// Forgot to borrow, e.g. `52 - 27 = 35`.
+    MissedBorrow,
+    /// Used another operation, e.g. `12 + 3` for `12 x 3`.
+    WrongOperation,
+    /// Swapped the digits, e.g. `63` for `36`.
+    DigitReversal,
+    /// Counted one too many or too few.
+    OffByOne,
+    /// Turned a fraction or a ratio upside down, e.g. `4/3` for `3/4`.
+    Inverted,
+    /// Put the decimal point in the wrong place, e.g. `3.5` for `0.35`.
+    MisplacedPoint,
+    /// Worked with 100 minutes to the hour, e.g. `290` minutes from `8:15` to `11:05`.
+    HundredMinutes,
+    /// Left out a prime factor, e.g. `3 x 5` for `45`.
+    MissedFactor,
+    /// Stopped at a factor that isn't prime, e.g. `9 x 5` for `45`.
+    CompositeFactor,
+}
+
+impl Misconception {
+    /// Stored in the `misconception` column.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Misconception::MissedCarry => "missed_carry",
+            Misconception::MissedBorrow => "missed_borrow",
+            Misconception::WrongOperation => "wrong_operation",
+            Misconception::DigitReversal => "digit_reversal",
+            Misconception::OffByOne => "off_by_one",
+            Misconception::Inverted => "inverted",
+            Misconception::MisplacedPoint => "misplaced_point",
+            Misconception::HundredMinutes => "hundred_minutes",
+            Misconception::MissedFactor => "missed_factor",
+            Misconception::CompositeFactor => "composite_factor",
+        }
+    }
+}
+
+/// One of the answers offered in multiple choice mode.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Choice {
+    pub answer: Answer,
+    /// The error leading to this answer, `None` for the correct answer and for the
+    /// alternatives of yes or no and comparison questions.
+    pub misconception: Option<Misconception>,
+}
+
+/// The choices of a question, stored as JSON in the `choices` column.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Choices(pub Vec<Choice>);
+
+impl Choices {
+    /// The expected answer and 3 distractors in random order, distractors the grading
+    /// policy accepts are left out. Yes or no and comparison questions offer every
+    /// possible answer. Empty if there aren't 3 plausible distractors, the question is
+    /// answered by typing then.
+    pub fn generate<R: Rng + ?Sized>(
+        kind: &QuestionKind,
+        expected: &Answer,
+        grading: &Grading,
+        rng: &mut R,
+    ) -> Self {
+        let mut choices = match expected {
+            Answer::Boolean(_) => vec![Answer::Boolean(true), Answer::Boolean(false)],
+            Answer::Relation(_) => vec![
+                Answer::Relation(Relation::Less),
+                Answer::Relation(Relation::Equal),
+                Answer::Relation(Relation::Greater),
+            ],
+            _ => vec![],
+        }
+        .into_iter()
+        .map(|answer| Choice {
+            answer,
+            misconception: None,
+        })
+        .collect::<Vec<_>>();
+        if choices.is_empty() {
+            choices.push(Choice {
+                answer: expected.clone(),
+                misconception: None,
+            });
+            let candidates = distractors(kind, expected)
+                .into_iter()
+                .chain(more_distractors(expected));
+            let divisor = divisor(kind);
+            for (answer, misconception) in candidates {
+                if choices.len() > 3 {
+                    break;
+                }
+                // Negative choices or remainders as large as the divisor would give the
+                // answer away
+                let remainder_too_large = match (&answer, divisor) {
+                    (Answer::QuotientRemainder { remainder, .. }, Some(divisor)) => {
+                        *remainder >= divisor
+                    }
+                    _ => false,
+                };
+                let gives_away =
+                    (is_negative(&answer) && !is_negative(expected)) || remainder_too_large;
+                if !gives_away
+                    && !grading.is_correct(expected, &answer)
+                    && choices.iter().all(|choice| choice.answer != answer)
+                {
+                    choices.push(Choice {
+                        answer,
+                        misconception: Some(misconception),
+                    });
+                }
+            }
+            if choices.len() < 4 {
+                return Self::default();
+            }
+        }
+        choices.shuffle(rng);
+        Self(choices)
+    }
+
+    /// The misconception behind the given answer, if it's one of the distractors.
+    pub fn misconception(&self, answer: &Answer) -> Option<Misconception> {
+        self.0
+            .iter()
+            .find(|choice| &choice.answer == answer)
+            .and_then(|choice| choice.misconception)
+    }
+}
+
+/// Wrong answers in order of preference, may contain duplicates.
+fn distractors(kind: &QuestionKind, expected: &Answer) -> Vec<(Answer, Misconception)> {
+    let mut result = vec![];
+    match expected {
+        Answer::Integer(n) => {
+            let integer = |value: i64, misconception| (Answer::Integer(value), misconception);
+            // A zero too many or too few, e.g. `32000` or `320` for `3.2 km = ? m`
+            if let QuestionKind::ConvertUnits { .. } = kind {
+                result.push(integer(n * 10, Misconception::MisplacedPoint));
+                if n % 10 == 0 {
+                    result.push(integer(n / 10, Misconception::MisplacedPoint));
+                }
+            }
+            // Subtracting the times like numbers, e.g. `1105 - 815`, or an hour off
+            if let QuestionKind::MinutesBetween { start, end } = kind {
+                let as_number = |minutes: i64| minutes / 60 * 100 + minutes % 60;
+                result.push(integer(
+                    as_number(*end) - as_number(*start),
+                    Misconception::HundredMinutes,
+                ));
+                result.push(integer(n + 60, Misconception::OffByOne));
+                result.push(integer(n - 60, Misconception::OffByOne));
+            }
+            // Errors in working out a single operation
+            if let QuestionKind::Evaluate(Expr::BinOp { op, lhs, rhs }) = kind {
+                if let (Some(a), Some(b)) = (lhs.eval(), rhs.eval()) {
+                    match op {
+                        Operator::Add if *n >= 10 => {
+                            result.push(integer(n - 10, Misconception::MissedCarry))
+                        }
+                        Operator::Sub => result.push(integer(n + 10, Misconception::MissedBorrow)),
+                        _ => {}
+                    }
+                    let wrong = match op {
+                        Operator::Add => Operator::Sub,
+                        Operator::Sub => Operator::Add,
+                        Operator::Mul => Operator::Add,
+                        Operator::Div => Operator::Sub,
+                    };
+                    if let Some(value) = Expr::binary(wrong, Expr::Frac(a), Expr::Frac(b))
+                        .eval()
+                        .and_then(|value| value.to_integer())
+                    {
+                        result.push(integer(value.abs(), Misconception::WrongOperation));
+                    }
+                }
+            }
+            if let Ok(reversed) = n
+                .abs()
+                .to_string()
+                .chars()
+                .rev()
+                .collect::<String>()
+                .parse::<i64>()
+            {
+                // Trailing zeros would be lost, e.g. `42` for `240000`
+                if n.abs() >= 10 && n % 10 != 0 {
+                    result.push(integer(reversed * n.signum(), Misconception::DigitReversal));
+                }
+            }
+            // A zero too many or too few, e.g. `2400000` for `estimate 586 x 416`
+            if let QuestionKind::Estimate(_) = kind {
+                result.push(integer(n * 10, Misconception::MisplacedPoint));
+                result.push(integer(n / 10, Misconception::MisplacedPoint));
+            }
+            // Answers to rounding are round, `more_distractors` keeps them that way
+            if !matches!(kind, QuestionKind::Round { .. } | QuestionKind::Estimate(_)) {
+                for offset in [1, -1, 2, -2, 10] {
+                    result.push(integer(n + offset, Misconception::OffByOne));
+                }
+            }
+        }
+        Answer::Fraction(r) => {
+            if let Some(inverted) = Rational::new(r.denom(), r.numer()) {
+                result.push((Answer::Fraction(inverted), Misconception::Inverted));
+            }
+            for offset in [1, -1, 2] {
+                if let Some(value) = Rational::new(r.numer() + offset, r.denom()) {
+                    result.push((Answer::Fraction(value), Misconception::OffByOne));
+                }
+                if let Some(value) = Rational::new(r.numer(), r.denom() + offset) {
+                    result.push((Answer::Fraction(value), Misconception::OffByOne));
+                }
+            }
+        }
+        Answer::Decimal(d) => {
+            let value = d.to_rational();
+            let ten = Rational::from(10);
+            let step = Rational::new(1, 10i64.pow(d.places())).unwrap_or(Rational::from(1));
+            let shifted = [value.checked_mul(ten), value.checked_div(ten)]
+                .map(|shifted| (shifted, Misconception::MisplacedPoint));
+            let near = [value.checked_add(step), value.checked_sub(step)]
+                .map(|near| (near, Misconception::OffByOne));
+            for (value, misconception) in shifted.into_iter().chain(near) {
+                if let Some(value) = value.and_then(Decimal::from_rational) {
+                    result.push((Answer::Decimal(value), misconception));
+                }
+            }
+        }
+        Answer::QuotientRemainder {
+            quotient,
+            remainder,
+        } => {
+            for (quotient, remainder) in [
+                (*quotient, remainder + 1),
+                (quotient + 1, *remainder),
+                (quotient - 1, *remainder),
+                (*quotient, remainder - 1),
+            ] {
+                result.push((
+                    Answer::QuotientRemainder {
+                        quotient,
+                        remainder,
+                    },
+                    Misconception::OffByOne,
+                ));
+            }
+        }
+        Answer::Money(money) => {
+            let amount = |minor| Answer::Money(Money::new(minor, money.currency));
+            // Points in the wrong place, e.g. `¥1.25` for `¥12.50`
+            result.push((amount(money.minor * 10), Misconception::MisplacedPoint));
+            if money.minor % 10 == 0 {
+                result.push((amount(money.minor / 10), Misconception::MisplacedPoint));
+            }
+            for offset in [100, -100, 10, -10] {
+                if money.minor + offset > 0 {
+                    result.push((amount(money.minor + offset), Misconception::OffByOne));
+                }
+            }
+        }
+        Answer::Time { hour, minute } => {
+            let time = |minutes: i64| Answer::Time {
+                hour: minutes.rem_euclid(1440) / 60,
+                minute: minutes.rem_euclid(60),
+            };
+            let minutes = hour * 60 + minute;
+            // Forgot to carry the hour, e.g. `12:15` for `10:30` plus `1h 45m`
+            if let QuestionKind::ClockTime { start, duration } = kind {
+                if start % 60 + duration % 60 >= 60 {
+                    result.push((time(minutes - 60), Misconception::MissedCarry));
+                } else if start % 60 + duration % 60 < 0 {
+                    result.push((time(minutes + 60), Misconception::MissedBorrow));
+                }
+            }
+            for offset in [60, -60, 10, -10] {
+                result.push((time(minutes + offset), Misconception::OffByOne));
+            }
+        }
+        Answer::Ratio(a, b) => {
+            result.push((Answer::Ratio(*b, *a), Misconception::Inverted));
+            result.push((Answer::Ratio(a + 1, *b), Misconception::OffByOne));
+            result.push((Answer::Ratio(*a, b + 1), Misconception::OffByOne));
+        }
+        Answer::IntegerList(factors) => {
+            let list = |mut factors: Vec<i64>| {
+                factors.sort_unstable();
+                Answer::IntegerList(factors)
+            };
+            for i in 0..factors.len() {
+                // Left out a factor, e.g. `3 x 5` for `3 x 3 x 5`
+                let mut missed = factors.clone();
+                missed.remove(i);
+                if !missed.is_empty() {
+                    result.push((list(missed), Misconception::MissedFactor));
+                }
+                // Multiplied two factors, e.g. `9 x 5` for `3 x 3 x 5`
+                if i + 1 < factors.len() {
+                    let mut composite = factors.clone();
+                    let product = composite.remove(i + 1);
+                    composite[i] *= product;
+                    result.push((list(composite), Misconception::CompositeFactor));
+                }
+                // A neighbouring prime, e.g. `3 x 3 x 7` for `3 x 3 x 5`
+                if let Some(neighbour) = ((factors[i] + 1)..).find(|&n| is_prime(n)) {
+                    let mut swapped = factors.clone();
+                    swapped[i] = neighbour;
+                    result.push((list(swapped), Misconception::OffByOne));
+                }
+            }
+        }
+        _ => {}
+    }
+    result
+}
+
+/// More wrong answers a step away from the expected answer, for when the typical
+/// errors give too few distractors or the grading policy accepts them,
+/// e.g. `270000` and `210000` for estimates of around `240000`.
+fn more_distractors(expected: &Answer) -> Vec<(Answer, Misconception)> {
+    let steps = (1..=20).flat_map(|k: i64| [k, -k]);
+    match expected {
+        Answer::Integer(n) => {
+            // Round numbers stay round, e.g. `4700` and `4500` for `4600`
+            let mut unit = 1;
+            while *n != 0 && n % (unit * 10) == 0 && unit < 1_000_000 {
+                unit *= 10;
+            }
+            steps
+                .filter_map(|k| n.checked_add(k * unit))
+                .map(Answer::Integer)
+                .collect()
+        }
+        Answer::Fraction(r) => steps
+            .filter_map(|k| Rational::new(r.numer().checked_add(k)?, r.denom()))
+            .map(Answer::Fraction)
+            .collect(),
+        Answer::Decimal(d) => {
+            let step = Rational::new(1, 10i64.pow(d.places())).unwrap_or(Rational::from(1));
+            steps
+                .filter_map(|k| {
+                    d.to_rational()
+                        .checked_add(step.checked_mul(Rational::from(k))?)
+                        .and_then(Decimal::from_rational)
+                })
+                .map(Answer::Decimal)
+                .collect()
+        }
+        Answer::QuotientRemainder {
+            quotient,
+            remainder,
+        } => steps
+            .flat_map(|k| [(quotient + k, *remainder), (*quotient, remainder + k)])
+            .map(|(quotient, remainder)| Answer::QuotientRemainder {
+                quotient,
+                remainder,
+            })
+            .collect(),
+        Answer::Money(money) => steps
+            .filter(|k| money.minor + k * 10 > 0)
+            .map(|k| Answer::Money(Money::new(money.minor + k * 10, money.currency)))
+            .collect(),
+        Answer::Time { hour, minute } => steps
+            .map(|k| (hour * 60 + minute + k * 5).rem_euclid(1440))
+            .map(|minutes| Answer::Time {
+                hour: minutes / 60,
+                minute: minutes % 60,
+            })
+            .collect(),
+        Answer::Ratio(a, b) => steps
+            .filter(|k| *k > 0)
+            .flat_map(|k| [Answer::Ratio(a + k, *b), Answer::Ratio(*a, b + k)])
+            .collect(),
+        _ => vec![],
+    }
+    .into_iter()
+    .map(|answer| (answer, Misconception::OffByOne))
+    .collect()
+}
+
+/// The divisor of a division, e.g. 6 for `17 ÷ 6`.
+fn divisor(kind: &QuestionKind) -> Option<i64> {
+    match kind {
+        QuestionKind::Evaluate(Expr::BinOp {
+            op: Operator::Div,
+            rhs,
+            ..
+        }) => rhs.eval()?.to_integer(),
+        _ => None,
+    }
+}
+
+/// Whether any part of the answer is below zero.
+fn is_negative(answer: &Answer) -> bool {
+    match answer {
+        Answer::QuotientRemainder {
+            quotient,
+            remainder,
+        } => *quotient < 0 || *remainder < 0,
+        Answer::Ratio(a, b) => *a < 0 || *b < 0,
+        Answer::IntegerList(numbers) => numbers.iter().any(|n| *n < 0),
+        _ => answer
+            .to_rational()
+            .is_some_and(|value| value < Rational::from(0)),
+    }
+}
+
+impl ToSql for Choices {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        serde_json::to_string(self)
+            .map(ToSqlOutput::from)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    }
+}
+
+impl FromSql for Choices {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        serde_json::from_str(value.as_str()?).map_err(|e| FromSqlError::Other(Box::new(e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::question_rng;
+
+    fn choices(kind: QuestionKind, expected: &Answer, grading: &Grading) -> Vec<Answer> {
+        Choices::generate(&kind, expected, grading, &mut question_rng(0, 0))
+            .0
+            .into_iter()
+            .map(|choice| choice.answer)
+            .collect()
+    }
+
+    fn assert_distinct_and_wrong(choices: &[Answer], expected: &Answer, grading: &Grading) {
+        assert_eq!(choices.len(), 4, "{:?}", choices);
+        assert_eq!(
+            choices
+                .iter()
+                .filter(|answer| grading.is_correct(expected, answer))
+                .count(),
+            1,
+            "{:?}",
+            choices
+        );
+        for (i, answer) in choices.iter().enumerate() {
+            assert!(!choices[i + 1..].contains(answer), "{:?}", choices);
+            assert!(!is_negative(answer), "{:?}", choices);
+        }
+    }
+
+    #[test]
+    fn leaves_out_answers_within_the_tolerance() {
+        let expected = Answer::Integer(240000);
+        let grading = Grading::Tolerance(Rational::from(24000));
+        let kind = QuestionKind::Estimate("586 x 416".parse().unwrap());
+        let choices = choices(kind, &expected, &grading);
+        assert_distinct_and_wrong(&choices, &expected, &grading);
+        assert!(choices.contains(&Answer::Integer(2400000)));
+        assert!(!choices.contains(&Answer::Integer(42)));
+    }
+
+    #[test]
+    fn factor_lists() {
+        let expected = Answer::IntegerList(vec![3, 3, 5]);
+        let grading = Grading::Unordered;
+        let choices = choices(QuestionKind::Factorize(45), &expected, &grading);
+        assert_distinct_and_wrong(&choices, &expected, &grading);
+        let misconceptions = Choices::generate(
+            &QuestionKind::Factorize(45),
+            &expected,
+            &grading,
+            &mut question_rng(0, 0),
+        );
+        assert_eq!(
+            misconceptions.misconception(&Answer::IntegerList(vec![3, 5])),
+            Some(Misconception::MissedFactor)
+        );
+        assert_eq!(
+            misconceptions.misconception(&Answer::IntegerList(vec![5, 9])),
+            Some(Misconception::CompositeFactor)
+        );
+    }
+
+    #[test]
+    fn too_few_distractors_fall_back_to_text_input() {
+        let expected = Answer::IntegerList(vec![7]);
+        assert!(choices(QuestionKind::Factorize(7), &expected, &Grading::Unordered).is_empty());
+    }
+
+    #[test]
+    fn no_negative_choices_for_zero() {
+        let integer = Answer::Integer(0);
+        let kind = QuestionKind::Evaluate("89 - 89".parse().unwrap());
+        let integers = choices(kind, &integer, &Grading::Exact);
+        assert_distinct_and_wrong(&integers, &integer, &Grading::Exact);
+
+        let decimal = Answer::Decimal(Decimal::from(0));
+        let operand = || Expr::dec("89.1".parse().unwrap());
+        let kind = QuestionKind::Evaluate(Expr::binary(Operator::Sub, operand(), operand()));
+        let decimals = choices(kind, &decimal, &Grading::Exact);
+        assert_distinct_and_wrong(&decimals, &decimal, &Grading::Exact);
+    }
+
+    #[test]
+    fn no_negative_remainders() {
+        for (quotient, remainder) in [(0, 0), (4, 0), (0, 3)] {
+            let expected = Answer::QuotientRemainder {
+                quotient,
+                remainder,
+            };
+            let kind = QuestionKind::Evaluate("9 ÷ 5".parse().unwrap());
+            let choices = choices(kind, &expected, &Grading::Exact);
+            assert_distinct_and_wrong(&choices, &expected, &Grading::Exact);
+        }
+    }
+
+    #[test]
+    fn remainders_stay_below_the_divisor() {
+        let expected = Answer::QuotientRemainder {
+            quotient: 2,
+            remainder: 5,
+        };
+        let kind = QuestionKind::Evaluate("17 ÷ 6".parse().unwrap());
+        let choices = choices(kind, &expected, &Grading::Exact);
+        assert_distinct_and_wrong(&choices, &expected, &Grading::Exact);
+        for answer in choices {
+            let Answer::QuotientRemainder { remainder, .. } = answer else {
+                panic!("{:?}", answer);
+            };
+            assert!(remainder < 6, "{:?}", answer);
+        }
+    }
+
+    #[test]
+    fn offers_every_relation() {
+        let expected = Answer::Relation(Relation::Less);
+        let kind = QuestionKind::Compare {
+            lhs: "5 + 3".parse().unwrap(),
+            rhs: "9".parse().unwrap(),
+        };
+        assert_eq!(choices(kind, &expected, &Grading::Exact).len(), 3);
+    }
+}
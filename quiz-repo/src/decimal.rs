@@ -0,0 +1,189 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::rational::Rational;
+
+/// An exact decimal number, `mantissa / 10^scale`.
+/// Trailing zeros are always stripped, so `3.50` equals `3.5`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Decimal {
+    mantissa: i64,
+    scale: u32,
+}
+
+impl Decimal {
+    pub fn new(mantissa: i64, scale: u32) -> Self {
+        let (mut mantissa, mut scale) = (mantissa, scale);
+        while scale > 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            scale -= 1;
+        }
+        Self { mantissa, scale }
+    }
+
+    /// Number of digits after the decimal point.
+    pub fn places(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn to_rational(&self) -> Rational {
+        Rational::new(self.mantissa, 10_i64.pow(self.scale)).unwrap()
+    }
+
+    /// Converts a fraction with a terminating decimal expansion,
+    /// `None` for values like `1/3`.
+    pub fn from_rational(r: Rational) -> Option<Self> {
+        let mut denom = r.denom();
+        let mut scale = 0;
+        let mut factor: i64 = 1;
+        // 10^scale must be a multiple of the denominator, which only works for 2s and 5s
+        for prime in [2, 5] {
+            let mut count = 0;
+            while denom % prime == 0 {
+                denom /= prime;
+                count += 1;
+            }
+            scale = scale.max(count);
+        }
+        if denom != 1 {
+            return None;
+        }
+        for _ in 0..scale {
+            factor = factor.checked_mul(10)?;
+        }
+        Some(Self::new(r.numer().checked_mul(factor / r.denom())?, scale))
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(n: i64) -> Self {
+        Self::new(n, 0)
+    }
+}
+
+/// `3.75`, or just `12` for whole numbers.
+impl Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.scale == 0 {
+            return write!(f, "{}", self.mantissa);
+        }
+        let digits = format!(
+            "{:0width$}",
+            self.mantissa.unsigned_abs(),
+            width = self.scale as usize + 1
+        );
+        let (whole, fraction) = digits.split_at(digits.len() - self.scale as usize);
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    }
+}
+
+/// Parses `3.75`, `.5`, `-1.2` or a whole number.
+impl FromStr for Decimal {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || anyhow::Error::msg(format!("Invalid decimal: {}", s));
+        let s = s.trim();
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        // 10^scale has to fit into an i64
+        if (whole.is_empty() && fraction.is_empty())
+            || fraction.len() > 18
+            || !whole
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let mantissa: i64 = format!("{}{}", whole, fraction)
+            .parse()
+            .map_err(|_| invalid())?;
+        Ok(Self::new(
+            if negative { -mantissa } else { mantissa },
+            fraction.len() as u32,
+        ))
+    }
+}
+
+impl From<Decimal> for String {
+    fn from(d: Decimal) -> Self {
+        d.to_string()
+    }
+}
+
+impl TryFrom<String> for Decimal {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_what_it_displays() {
+        for text in ["0", "12", "-7", "3.75", "0.05", "-0.4", "-12.001"] {
+            assert_eq!(d(text).to_string(), text);
+        }
+        assert_eq!(d("9223372036854775807").to_string(), "9223372036854775807");
+        assert_eq!(
+            d("0.000000000000000001").to_string(),
+            "0.000000000000000001"
+        );
+    }
+
+    #[test]
+    fn strips_trailing_zeros() {
+        assert_eq!(d("3.50"), d("3.5"));
+        assert_eq!(d("3.50").places(), 1);
+        assert_eq!(d("2.000"), Decimal::from(2));
+        assert_eq!(d(".5"), d("0.5"));
+        assert_eq!(d("5."), Decimal::from(5));
+        assert_eq!(d("-0.0"), Decimal::from(0));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        for text in [
+            "",
+            ".",
+            "-",
+            "1.2.3",
+            "1,5",
+            "+1",
+            "--1",
+            "1e3",
+            "9223372036854775808",
+            "-9223372036854775808",
+            "0.0000000000000000001",
+        ] {
+            assert!(text.parse::<Decimal>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn converts_rationals() {
+        let r = |numer, denom| Rational::new(numer, denom).unwrap();
+        assert_eq!(d("0.375").to_rational(), r(3, 8));
+        assert_eq!(d("-1.25").to_rational(), r(-5, 4));
+        assert_eq!(Decimal::from_rational(r(3, 8)), Some(d("0.375")));
+        assert_eq!(Decimal::from_rational(r(7, 20)), Some(d("0.35")));
+        assert_eq!(Decimal::from_rational(r(1, 3)), None);
+        assert_eq!(Decimal::from_rational(r(1, 1 << 62)), None);
+        assert_eq!(Decimal::from_rational(r(i64::MAX, 2)), None);
+    }
+}
@@ -0,0 +1,591 @@
+use std::{fmt::Display, str::FromStr};
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    decimal::Decimal,
+    rational::Rational,
+    regrouping::{count_borrows, count_carries, Regrouping},
+};
+
+/// The symbols used when rendering an expression.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Notation {
+    /// `12 x 34`, `96 ÷ 8`, the notation questions are stored in.
+    #[default]
+    Plain,
+    /// `12 × 34`, `96 ÷ 8`
+    Unicode,
+    /// `12 * 34`, `96 / 8`
+    Ascii,
+    /// `12 \times 34`, `96 \div 8`
+    Latex,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Operator {
+    pub fn symbol(&self, notation: Notation) -> &'static str {
+        match (self, notation) {
+            (Operator::Add, _) => "+",
+            (Operator::Sub, _) => "-",
+            (Operator::Mul, Notation::Plain) => "x",
+            (Operator::Mul, Notation::Unicode) => "×",
+            (Operator::Mul, Notation::Ascii) => "*",
+            (Operator::Mul, Notation::Latex) => "\\times",
+            (Operator::Div, Notation::Ascii) => "/",
+            (Operator::Div, Notation::Latex) => "\\div",
+            (Operator::Div, _) => "÷",
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Operator::Add | Operator::Sub => 1,
+            Operator::Mul | Operator::Div => 2,
+        }
+    }
+
+    /// Applies the operator, `None` on overflow or division by zero.
+    fn apply(&self, a: Rational, b: Rational) -> Option<Rational> {
+        match self {
+            Operator::Add => a.checked_add(b),
+            Operator::Sub => a.checked_sub(b),
+            Operator::Mul => a.checked_mul(b),
+            Operator::Div => a.checked_div(b),
+        }
+    }
+}
+
+/// An arithmetic expression tree.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Expr {
+    Num(i64),
+    /// A fraction operand like `3/4`.
+    Frac(Rational),
+    /// A decimal operand like `3.75`.
+    Dec(Decimal),
+    BinOp {
+        op: Operator,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    /// A power like `7²`.
+    Pow {
+        base: Box<Expr>,
+        exponent: u32,
+    },
+    /// A root like `√144`, `index` is 2 for square roots and 3 for cube roots.
+    Root {
+        radicand: Box<Expr>,
+        index: u32,
+    },
+    /// The unknown of an equation, e.g. `x` in `3x + 7 = 25`.
+    Var(char),
+}
+
+const SUPERSCRIPT_DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+fn superscript(n: u32) -> String {
+    n.to_string()
+        .chars()
+        .map(|c| SUPERSCRIPT_DIGITS[c.to_digit(10).unwrap() as usize])
+        .collect()
+}
+
+impl Expr {
+    pub fn num(n: i64) -> Self {
+        Expr::Num(n)
+    }
+
+    pub fn frac(r: Rational) -> Self {
+        Expr::Frac(r)
+    }
+
+    pub fn dec(d: Decimal) -> Self {
+        Expr::Dec(d)
+    }
+
+    pub fn binary(op: Operator, lhs: Expr, rhs: Expr) -> Self {
+        Expr::BinOp {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    pub fn pow(base: Expr, exponent: u32) -> Self {
+        Expr::Pow {
+            base: Box::new(base),
+            exponent,
+        }
+    }
+
+    pub fn root(radicand: Expr, index: u32) -> Self {
+        Expr::Root {
+            radicand: Box::new(radicand),
+            index,
+        }
+    }
+
+    pub fn var(name: char) -> Self {
+        Expr::Var(name)
+    }
+
+    /// Evaluates the expression exactly, `None` on overflow, division by zero or
+    /// if it contains a variable.
+    pub fn eval(&self) -> Option<Rational> {
+        match self {
+            Expr::Num(n) => Some(Rational::from(*n)),
+            Expr::Frac(r) => Some(*r),
+            Expr::Dec(d) => Some(d.to_rational()),
+            Expr::BinOp { op, lhs, rhs } => op.apply(lhs.eval()?, rhs.eval()?),
+            Expr::Pow { base, exponent } => base.eval()?.checked_pow(*exponent),
+            // Only exact roots have a value
+            Expr::Root { radicand, index } => radicand.eval()?.checked_root(*index),
+            Expr::Var(_) => None,
+        }
+    }
+
+    pub fn has_var(&self) -> bool {
+        match self {
+            Expr::Var(_) => true,
+            Expr::BinOp { lhs, rhs, .. } => lhs.has_var() || rhs.has_var(),
+            Expr::Pow { base, .. } => base.has_var(),
+            Expr::Root { radicand, .. } => radicand.has_var(),
+            _ => false,
+        }
+    }
+
+    /// Writes an expression with one variable as `a * x + b`, returns `(a, b)`.
+    /// `None` if it isn't linear in the variable, or on overflow or division by zero.
+    pub fn linear(&self) -> Option<(Rational, Rational)> {
+        let zero = Rational::from(0);
+        match self {
+            Expr::Var(_) => Some((Rational::from(1), zero)),
+            Expr::BinOp { op, lhs, rhs } => {
+                let ((a1, b1), (a2, b2)) = (lhs.linear()?, rhs.linear()?);
+                match op {
+                    Operator::Add => Some((a1.checked_add(a2)?, b1.checked_add(b2)?)),
+                    Operator::Sub => Some((a1.checked_sub(a2)?, b1.checked_sub(b2)?)),
+                    Operator::Mul if a1 == zero => Some((b1.checked_mul(a2)?, b1.checked_mul(b2)?)),
+                    Operator::Mul if a2 == zero => Some((a1.checked_mul(b2)?, b1.checked_mul(b2)?)),
+                    Operator::Div if a2 == zero => Some((a1.checked_div(b2)?, b1.checked_div(b2)?)),
+                    _ => None,
+                }
+            }
+            _ if self.has_var() => None,
+            _ => Some((zero, self.eval()?)),
+        }
+    }
+
+    /// The operands from left to right, powers and roots count as one operand.
+    pub fn operands(&self) -> Vec<&Expr> {
+        match self {
+            Expr::BinOp { lhs, rhs, .. } => {
+                let mut operands = lhs.operands();
+                operands.extend(rhs.operands());
+                operands
+            }
+            _ => vec![self],
+        }
+    }
+
+    /// Carries or borrows needed to work out a single addition or subtraction of
+    /// non-negative integers in columns, `None` for any other expression.
+    pub fn regroupings(&self) -> Option<(Regrouping, u32)> {
+        match self {
+            Expr::BinOp { op, lhs, rhs } => match (op, lhs.as_ref(), rhs.as_ref()) {
+                (Operator::Add, Expr::Num(a), Expr::Num(b)) if *a >= 0 && *b >= 0 => {
+                    Some((Regrouping::Carry, count_carries(*a as u64, *b as u64)))
+                }
+                (Operator::Sub, Expr::Num(a), Expr::Num(b)) if *a >= *b && *b >= 0 => {
+                    Some((Regrouping::Borrow, count_borrows(*a as u64, *b as u64)))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Renders the expression, adding parentheses only where the precedence requires them.
+    pub fn render(&self, notation: Notation) -> String {
+        self.render_inner(notation, None, &mut 0)
+    }
+
+    /// Renders the expression with the operand at `blank` (counted from the left) shown as `?`.
+    pub fn render_with_blank(&self, notation: Notation, blank: usize) -> String {
+        self.render_inner(notation, Some(blank), &mut 0)
+    }
+
+    fn render_inner(&self, notation: Notation, blank: Option<usize>, next: &mut usize) -> String {
+        let index = *next;
+        if !matches!(self, Expr::BinOp { .. }) {
+            *next += 1;
+            if blank == Some(index) {
+                return "?".to_string();
+            }
+        }
+        match self {
+            // Only the leading operand keeps a bare minus sign, i.e. `-6 x (-4)`
+            Expr::Num(n) if *n < 0 && index > 0 => match notation {
+                Notation::Latex => format!("\\left({}\\right)", n),
+                _ => format!("({})", n),
+            },
+            Expr::Num(n) => n.to_string(),
+            Expr::Frac(r) => match notation {
+                Notation::Latex => format!("\\frac{{{}}}{{{}}}", r.numer(), r.denom()),
+                _ => r.to_string(),
+            },
+            Expr::Dec(d) => d.to_string(),
+            Expr::BinOp { op, lhs, rhs } => {
+                // A coefficient goes right before the variable or the group, e.g. `3x` or `2(x - 4)`
+                let implicit =
+                    *op == Operator::Mul && matches!(lhs.as_ref(), Expr::Num(_)) && rhs.has_var();
+                let lhs = lhs.render_operand(notation, op.precedence(), false, blank, next);
+                let rhs = rhs.render_operand(notation, op.precedence(), true, blank, next);
+                if implicit {
+                    format!("{}{}", lhs, rhs)
+                } else {
+                    format!("{} {} {}", lhs, op.symbol(notation), rhs)
+                }
+            }
+            Expr::Pow { base, exponent } => {
+                let base = base.render_grouped(notation);
+                match notation {
+                    Notation::Plain | Notation::Unicode => {
+                        format!("{}{}", base, superscript(*exponent))
+                    }
+                    Notation::Ascii => format!("{}^{}", base, exponent),
+                    Notation::Latex => format!("{}^{{{}}}", base, exponent),
+                }
+            }
+            Expr::Root { radicand, index } => match (notation, index) {
+                (Notation::Plain | Notation::Unicode, 2) => {
+                    format!("√{}", radicand.render_grouped(notation))
+                }
+                (Notation::Plain | Notation::Unicode, 3) => {
+                    format!("∛{}", radicand.render_grouped(notation))
+                }
+                (Notation::Plain | Notation::Unicode, _) => format!(
+                    "{}√{}",
+                    superscript(*index),
+                    radicand.render_grouped(notation)
+                ),
+                (Notation::Ascii, 2) => format!("sqrt({})", radicand.render(notation)),
+                (Notation::Ascii, 3) => format!("cbrt({})", radicand.render(notation)),
+                (Notation::Ascii, _) => {
+                    format!("{}^(1/{})", radicand.render_grouped(notation), index)
+                }
+                (Notation::Latex, 2) => format!("\\sqrt{{{}}}", radicand.render(notation)),
+                (Notation::Latex, _) => {
+                    format!("\\sqrt[{}]{{{}}}", index, radicand.render(notation))
+                }
+            },
+            Expr::Var(name) => name.to_string(),
+        }
+    }
+
+    /// Renders the base of a power or the radicand of a root,
+    /// with parentheses unless it's a single non-negative number.
+    fn render_grouped(&self, notation: Notation) -> String {
+        let text = self.render(notation);
+        match self {
+            Expr::Num(n) if *n >= 0 => text,
+            Expr::Var(_) => text,
+            Expr::Dec(d) if d.to_rational() >= Rational::from(0) => text,
+            _ => match notation {
+                Notation::Latex => format!("\\left({}\\right)", text),
+                _ => format!("({})", text),
+            },
+        }
+    }
+
+    fn render_operand(
+        &self,
+        notation: Notation,
+        parent: u8,
+        right: bool,
+        blank: Option<usize>,
+        next: &mut usize,
+    ) -> String {
+        let text = self.render_inner(notation, blank, next);
+        match self {
+            // The right operand of `-` and `÷` needs parentheses on the same level too
+            Expr::BinOp { op, .. }
+                if op.precedence() < parent || (right && op.precedence() == parent) =>
+            {
+                match notation {
+                    Notation::Latex => format!("\\left({}\\right)", text),
+                    _ => format!("({})", text),
+                }
+            }
+            _ => text,
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(Notation::Plain))
+    }
+}
+
+/// Parses the plain, unicode or ascii notation, e.g. `(35 + 17) x 4 - 12`, `-6 x (-4)`,
+/// `7² + √144` or `7^2 + sqrt(144)`.
+impl FromStr for Expr {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.chars().filter(|c| !c.is_whitespace()).collect(),
+            pos: 0,
+        };
+        let expr = parser.parse_sum()?;
+        if parser.pos != parser.chars.len() {
+            anyhow::bail!("Unexpected trailing input in expression: {}", s);
+        }
+        Ok(expr)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn parse_sum(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.parse_product()?;
+        loop {
+            let op = match self.peek() {
+                Some('+') => Operator::Add,
+                Some('-') => Operator::Sub,
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = Expr::binary(op, expr, self.parse_product()?);
+        }
+    }
+
+    fn parse_product(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.parse_power()?;
+        loop {
+            let op = match self.peek() {
+                Some('x' | '×' | '*') => Operator::Mul,
+                Some('÷' | '/') => Operator::Div,
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = Expr::binary(op, expr, self.parse_power()?);
+        }
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        prefix
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    /// An operand, optionally with a root in front or an exponent behind it.
+    fn parse_power(&mut self) -> anyhow::Result<Expr> {
+        for (prefix, index) in [("√", 2), ("sqrt", 2), ("∛", 3), ("cbrt", 3)] {
+            if self.starts_with(prefix) {
+                self.pos += prefix.chars().count();
+                return Ok(Expr::root(self.parse_power()?, index));
+            }
+        }
+        let mut expr = self.parse_operand()?;
+        loop {
+            let start = self.pos;
+            let exponent: String = if self.peek() == Some('^') {
+                self.pos += 1;
+                let digits_start = self.pos;
+                while self.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) {
+                    self.pos += 1;
+                }
+                self.chars[digits_start..self.pos].iter().collect()
+            } else {
+                let mut digits = String::new();
+                while let Some(digit) = self
+                    .peek()
+                    .and_then(|c| SUPERSCRIPT_DIGITS.iter().position(|d| *d == c))
+                {
+                    digits.push_str(&digit.to_string());
+                    self.pos += 1;
+                }
+                digits
+            };
+            if self.pos == start {
+                return Ok(expr);
+            }
+            expr = Expr::pow(
+                expr,
+                exponent.parse().map_err(|_| {
+                    anyhow::Error::msg(format!("Expected an exponent at position {}", start))
+                })?,
+            );
+        }
+    }
+
+    fn parse_operand(&mut self) -> anyhow::Result<Expr> {
+        if self.peek() == Some('(') {
+            self.pos += 1;
+            let expr = self.parse_sum()?;
+            if self.peek() != Some(')') {
+                anyhow::bail!("Missing closing parenthesis");
+            }
+            self.pos += 1;
+            return Ok(expr);
+        }
+        let start = self.pos;
+        // A minus sign in front of a number makes it negative
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        while self.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        Ok(Expr::Num(digits.parse().map_err(|_| {
+            anyhow::Error::msg(format!("Expected a number at position {}", start))
+        })?))
+    }
+}
+
+impl ToSql for Expr {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        serde_json::to_string(self)
+            .map(ToSqlOutput::from)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    }
+}
+
+impl FromSql for Expr {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        serde_json::from_str(value.as_str()?).map_err(|e| FromSqlError::Other(Box::new(e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Expr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_what_it_renders() {
+        for text in [
+            "38 + 47",
+            "90 - 45",
+            "12 x 34",
+            "96 ÷ 8",
+            "(35 + 17) x 4 - 12",
+            "100 - (20 + 30)",
+            "48 ÷ (2 x 3)",
+            "-6 x (-4)",
+            "7² + √144",
+            "∛27 x 2³",
+            "(-3)²",
+        ] {
+            assert_eq!(parse(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn parses_other_notations() {
+        assert_eq!(parse("12*34"), parse("12 × 34"));
+        assert_eq!(parse("96/8"), parse("96 ÷ 8"));
+        assert_eq!(parse("7^2 + sqrt(144)"), parse("7² + √144"));
+        assert_eq!(parse("cbrt(27)"), parse("∛27"));
+    }
+
+    #[test]
+    fn renders_other_notations() {
+        let expr = parse("(35 + 17) x 4 ÷ √16");
+        assert_eq!(expr.render(Notation::Unicode), "(35 + 17) × 4 ÷ √16");
+        assert_eq!(expr.render(Notation::Ascii), "(35 + 17) * 4 / sqrt(16)");
+        assert_eq!(
+            expr.render(Notation::Latex),
+            "\\left(35 + 17\\right) \\times 4 \\div \\sqrt{16}"
+        );
+    }
+
+    #[test]
+    fn operators_are_left_associative() {
+        assert_eq!(parse("20 - 5 - 3").eval(), Some(Rational::from(12)));
+        assert_eq!(parse("20 - (5 - 3)").eval(), Some(Rational::from(18)));
+        assert_eq!(parse("48 ÷ 4 ÷ 2").eval(), Some(Rational::from(6)));
+        assert_eq!(parse("2 + 3 x 4").eval(), Some(Rational::from(14)));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        for text in [
+            "",
+            "1 +",
+            "(1 + 2",
+            "1 + 2)",
+            "12 x abc",
+            "9223372036854775808",
+        ] {
+            assert!(text.parse::<Expr>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn eval_is_none_on_overflow_and_division_by_zero() {
+        assert_eq!(parse("9223372036854775807 + 1").eval(), None);
+        assert_eq!(parse("-9223372036854775807 - 2").eval(), None);
+        assert_eq!(parse("5 ÷ (3 - 3)").eval(), None);
+        assert_eq!(parse("10^20").eval(), None);
+        assert_eq!(parse("√2").eval(), None);
+    }
+
+    #[test]
+    fn blank_replaces_the_operand() {
+        let expr = parse("35 + 17 x 4");
+        assert_eq!(expr.render_with_blank(Notation::Plain, 0), "? + 17 x 4");
+        assert_eq!(expr.render_with_blank(Notation::Plain, 2), "35 + 17 x ?");
+    }
+
+    #[test]
+    fn counts_regroupings() {
+        assert_eq!(parse("38 + 47").regroupings(), Some((Regrouping::Carry, 1)));
+        assert_eq!(parse("999 + 1").regroupings(), Some((Regrouping::Carry, 3)));
+        assert_eq!(
+            parse("90 - 45").regroupings(),
+            Some((Regrouping::Borrow, 1))
+        );
+        assert_eq!(parse("45 - 90").regroupings(), None);
+        assert_eq!(parse("12 x 3").regroupings(), None);
+    }
+
+    #[test]
+    fn linear_form() {
+        let r = Rational::from;
+        let expr = Expr::binary(
+            Operator::Add,
+            Expr::binary(Operator::Mul, Expr::num(3), Expr::var('x')),
+            Expr::num(7),
+        );
+        assert_eq!(expr.to_string(), "3x + 7");
+        assert_eq!(expr.linear(), Some((r(3), r(7))));
+        let square = Expr::binary(Operator::Mul, Expr::var('x'), Expr::var('x'));
+        assert_eq!(square.linear(), None);
+    }
+}
@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use rand::{distributions::WeightedIndex, prelude::Distribution, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
+
+use crate::{
+    answer::Answer, builtin, grading::Grading, kind::QuestionKind, profile::DifficultyProfile,
+};
+
+/// Creates questions of one topic, e.g. fractions.
+///
+/// `generate` takes a `dyn RngCore` so generators can be kept in a `GeneratorRegistry`,
+/// any `&mut impl Rng` converts to it.
+pub trait QuestionGenerator: Send + Sync {
+    /// Unique name, stored together with every generated question.
+    fn name(&self) -> &'static str;
+
+    /// The parameters of the generator, together with their current values.
+    fn parameters(&self) -> Vec<Parameter>;
+
+    /// Relative chance of picking this generator, 0 disables it.
+    fn weight(&self) -> u32;
+
+    fn generate(&self, rng: &mut dyn RngCore) -> (QuestionKind, Answer);
+
+    /// How answers to a generated question are graded, exactly unless overridden.
+    fn grading(&self, _kind: &QuestionKind, _answer: &Answer) -> Grading {
+        Grading::Exact
+    }
+}
+
+/// Type of a generator parameter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParameterType {
+    Integer,
+    /// An inclusive range, the value is `{"start": 1, "end": 20}`.
+    Range,
+    Boolean,
+    IntegerList,
+    Text,
+    TextList,
+    /// One of the given strings, or null.
+    Choice(&'static [&'static str]),
+}
+
+/// Describes one parameter of a generator.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub description: &'static str,
+    #[serde(rename = "type")]
+    pub parameter_type: ParameterType,
+    pub value: serde_json::Value,
+}
+
+impl Parameter {
+    pub fn new(
+        name: &'static str,
+        description: &'static str,
+        parameter_type: ParameterType,
+        value: serde_json::Value,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            parameter_type,
+            value,
+        }
+    }
+}
+
+/// The generators `QuizRepo` picks questions from, weighted by `QuestionGenerator::weight`.
+#[derive(Clone, Default)]
+pub struct GeneratorRegistry {
+    generators: Vec<Arc<dyn QuestionGenerator>>,
+}
+
+impl GeneratorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in generators, configured by the profile.
+    pub fn from_profile(profile: &DifficultyProfile) -> Self {
+        let mut registry = Self::new();
+        builtin::register(&mut registry, profile);
+        registry
+    }
+
+    /// Adds a generator, replacing the one with the same name.
+    pub fn register<G: QuestionGenerator + 'static>(&mut self, generator: G) -> &mut Self {
+        let generator: Arc<dyn QuestionGenerator> = Arc::new(generator);
+        match self
+            .generators
+            .iter_mut()
+            .find(|g| g.name() == generator.name())
+        {
+            Some(existing) => *existing = generator,
+            None => self.generators.push(generator),
+        }
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn QuestionGenerator> {
+        self.generators
+            .iter()
+            .find(|g| g.name() == name)
+            .map(|g| g.as_ref())
+    }
+
+    pub fn generators(&self) -> impl Iterator<Item = &dyn QuestionGenerator> {
+        self.generators.iter().map(|g| g.as_ref())
+    }
+
+    /// Names of the generators with a weight above 0.
+    pub fn enabled(&self) -> Vec<&'static str> {
+        self.generators()
+            .filter(|g| g.weight() > 0)
+            .map(|g| g.name())
+            .collect()
+    }
+
+    /// Picks a generator by weight and generates a question with it,
+    /// falls back to the first generator if every weight is 0.
+    /// Returns `None` if the registry is empty.
+    pub fn generate(
+        &self,
+        rng: &mut dyn RngCore,
+    ) -> Option<(&dyn QuestionGenerator, QuestionKind, Answer)> {
+        let index = WeightedIndex::new(self.generators.iter().map(|g| g.weight()))
+            .map(|distribution| distribution.sample(rng))
+            .unwrap_or(0);
+        let generator = self.generators.get(index)?;
+        let (kind, answer) = generator.generate(rng);
+        Some((generator.as_ref(), kind, answer))
+    }
+}
+
+/// The random number generator for question `index` of a session with the given seed.
+/// The same seed and index always give the same numbers, on every platform.
+pub fn question_rng(seed: u32, index: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed.into());
+    rng.set_stream(index);
+    rng
+}
+
+/// The random number generator for the choices of question `index` of a session,
+/// far past the numbers the question itself is drawn with.
+pub(crate) fn choices_rng(seed: u32, index: u64) -> ChaCha8Rng {
+    let mut rng = question_rng(seed, index);
+    rng.set_word_pos(1 << 64);
+    rng
+}
@@ -0,0 +1,195 @@
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+
+use crate::{answer::Answer, rational::Rational};
+
+/// How the answer to a question is graded, stored as JSON in the `grading` column.
+/// Questions without a grading policy are graded exactly.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Grading {
+    /// The answer must equal the expected answer.
+    #[default]
+    Exact,
+    /// Numbers at most this far from the expected answer are accepted,
+    /// e.g. `estimate 398 x 21` accepts 7600 to 8400 with a tolerance of 400.
+    Tolerance(Rational),
+    /// These answers are accepted besides the expected answer.
+    AcceptedSet(Vec<Answer>),
+    /// Lists are accepted in any order, e.g. `3 x 2 x 2` for the prime factors of 12.
+    Unordered,
+}
+
+impl Grading {
+    pub fn is_correct(&self, expected: &Answer, given: &Answer) -> bool {
+        if expected == given {
+            return true;
+        }
+        match self {
+            Grading::Exact => false,
+            Grading::Tolerance(tolerance) => match (expected.to_rational(), given.to_rational()) {
+                (Some(expected), Some(given)) => {
+                    expected
+                        .checked_sub(*tolerance)
+                        .is_some_and(|min| min <= given)
+                        && expected
+                            .checked_add(*tolerance)
+                            .is_some_and(|max| given <= max)
+                }
+                _ => false,
+            },
+            Grading::AcceptedSet(answers) => answers.contains(given),
+            Grading::Unordered => match (expected, given) {
+                (Answer::IntegerList(expected), Answer::IntegerList(given)) => {
+                    let (mut expected, mut given) = (expected.clone(), given.clone());
+                    expected.sort_unstable();
+                    given.sort_unstable();
+                    expected == given
+                }
+                _ => false,
+            },
+        }
+    }
+}
+
+impl ToSql for Grading {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        serde_json::to_string(self)
+            .map(ToSqlOutput::from)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    }
+}
+
+impl FromSql for Grading {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Null => Ok(Grading::Exact),
+            _ => {
+                serde_json::from_str(value.as_str()?).map_err(|e| FromSqlError::Other(Box::new(e)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact() {
+        let grading = Grading::Exact;
+        assert!(grading.is_correct(&Answer::Integer(85), &Answer::Integer(85)));
+        assert!(!grading.is_correct(&Answer::Integer(85), &Answer::Integer(84)));
+        let half = Answer::Fraction(Rational::new(1, 2).unwrap());
+        assert!(grading.is_correct(&half, &Answer::Fraction(Rational::new(2, 4).unwrap())));
+    }
+
+    #[test]
+    fn tolerance() {
+        let grading = Grading::Tolerance(Rational::from(400));
+        let expected = Answer::Integer(8000);
+        for given in [7600, 7999, 8000, 8400] {
+            assert!(
+                grading.is_correct(&expected, &Answer::Integer(given)),
+                "{}",
+                given
+            );
+        }
+        for given in [7599, 8401, -8000] {
+            assert!(
+                !grading.is_correct(&expected, &Answer::Integer(given)),
+                "{}",
+                given
+            );
+        }
+        // Other numeric answers are compared by value
+        let decimal = Answer::Decimal("8399.5".parse().unwrap());
+        assert!(grading.is_correct(&expected, &decimal));
+        assert!(!grading.is_correct(&expected, &Answer::IntegerList(vec![8000])));
+    }
+
+    #[test]
+    fn tolerance_near_the_limits() {
+        let grading = Grading::Tolerance(Rational::from(10));
+        let expected = Answer::Integer(i64::MAX - 5);
+        assert!(!grading.is_correct(&expected, &Answer::Integer(i64::MAX)));
+        let expected = Answer::Integer(i64::MIN);
+        assert!(!grading.is_correct(&expected, &Answer::Integer(i64::MIN + 1)));
+        assert!(grading.is_correct(&expected, &expected));
+    }
+
+    #[test]
+    fn accepted_set() {
+        let grading = Grading::AcceptedSet(vec![Answer::Time {
+            hour: 1,
+            minute: 15,
+        }]);
+        let expected = Answer::Time {
+            hour: 13,
+            minute: 15,
+        };
+        assert!(grading.is_correct(&expected, &expected));
+        assert!(grading.is_correct(
+            &expected,
+            &Answer::Time {
+                hour: 1,
+                minute: 15
+            }
+        ));
+        assert!(!grading.is_correct(
+            &expected,
+            &Answer::Time {
+                hour: 1,
+                minute: 16
+            }
+        ));
+    }
+
+    #[test]
+    fn unordered() {
+        let grading = Grading::Unordered;
+        let expected = Answer::IntegerList(vec![2, 2, 3]);
+        assert!(grading.is_correct(&expected, &Answer::IntegerList(vec![3, 2, 2])));
+        assert!(grading.is_correct(&expected, &Answer::IntegerList(vec![2, 3, 2])));
+        assert!(!grading.is_correct(&expected, &Answer::IntegerList(vec![2, 3])));
+        assert!(!grading.is_correct(&expected, &Answer::IntegerList(vec![2, 3, 3])));
+        assert!(!grading.is_correct(&expected, &Answer::Integer(12)));
+        assert!(!Grading::Exact.is_correct(&expected, &Answer::IntegerList(vec![3, 2, 2])));
+    }
+
+    #[test]
+    fn quotient_and_remainder_are_both_graded() {
+        // 47 ÷ 5 = 9 R 2
+        let expected = Answer::QuotientRemainder {
+            quotient: 9,
+            remainder: 2,
+        };
+        let parse = |input| expected.parse_input(input).unwrap();
+        for grading in [Grading::Exact, Grading::Tolerance(Rational::from(1))] {
+            assert!(grading.is_correct(&expected, &parse("9 R 2")));
+            assert!(!grading.is_correct(&expected, &parse("9 R 3")));
+            assert!(!grading.is_correct(&expected, &parse("8 R 2")));
+            assert!(!grading.is_correct(&expected, &parse("8 R 7")));
+        }
+        assert_eq!(expected.wrong_parts(&parse("9 R 3")), vec!["remainder"]);
+        assert_eq!(expected.wrong_parts(&parse("10 R 2")), vec!["quotient"]);
+    }
+
+    #[test]
+    fn survives_sql_round_trip() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        for grading in [
+            Grading::Exact,
+            Grading::Tolerance(Rational::new(1, 2).unwrap()),
+            Grading::AcceptedSet(vec![Answer::Integer(3), Answer::Boolean(true)]),
+            Grading::Unordered,
+        ] {
+            let stored: Grading = conn
+                .query_row("SELECT ?1", [&grading], |row| row.get(0))
+                .unwrap();
+            assert_eq!(stored, grading);
+        }
+        let legacy: Grading = conn.query_row("SELECT NULL", [], |row| row.get(0)).unwrap();
+        assert_eq!(legacy, Grading::Exact);
+    }
+}
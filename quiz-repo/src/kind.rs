@@ -0,0 +1,418 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    decimal::Decimal,
+    expr::{Expr, Notation},
+    money::Money,
+    rational::Rational,
+    regrouping::Regrouping,
+    unit::{Unit, UnitFamily},
+};
+
+/// What the user is asked to do with a question.
+///
+/// The name of the variant is stored in the `kind` column, the content in the
+/// `expression` column. Rows without a kind were written before other kinds existed
+/// and hold the expression of an `Evaluate` question.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "body", rename_all = "snake_case")]
+pub enum QuestionKind {
+    /// Compute the value of the expression, e.g. `12 x 34 = ?`.
+    Evaluate(Expr),
+    /// Find the operand at `blank` (counted from the left), e.g. `? + 37 = 120`.
+    MissingOperand { expression: Expr, blank: usize },
+    /// Take a percentage of a number, e.g. `25% of 80`.
+    PercentOf { percent: i64, whole: i64 },
+    /// Find which percentage of `whole` is `part`, e.g. `what percent of 60 is 15`.
+    WhatPercent { part: i64, whole: i64 },
+    /// Reduce a ratio to lowest terms, e.g. `simplify 12:18`.
+    SimplifyRatio { antecedent: i64, consequent: i64 },
+    /// Round a number to a power of ten, e.g. `round 4,587 to the nearest hundred`.
+    Round { number: i64, place: i64 },
+    /// Estimate the value of the expression, e.g. `estimate 398 x 21`.
+    Estimate(Expr),
+    /// Find the greatest common divisor, e.g. `GCD of 12 and 18`.
+    Gcd { a: i64, b: i64 },
+    /// Find the least common multiple, e.g. `LCM of 4 and 6`.
+    Lcm { a: i64, b: i64 },
+    /// Tell whether the number is prime, e.g. `is 91 prime`.
+    IsPrime(i64),
+    /// Write the number as a product of primes, e.g. `factor 84 into primes`.
+    Factorize(i64),
+    /// Solve a linear equation for `x`, e.g. `3x + 7 = 25` or `2(x - 4) = 10`.
+    Equation { lhs: Expr, rhs: Expr },
+    /// Find the next term of an arithmetic or geometric sequence, e.g. `3, 7, 11, 15, ?`.
+    Sequence { terms: Vec<i64> },
+    /// Pick the relation between two expressions, e.g. `38 + 47 ☐ 9 x 9`.
+    Compare { lhs: Expr, rhs: Expr },
+    /// A problem told in words, filled in from the template with the given name, e.g.
+    /// `Mia has 12 apples and buys 7 more. How many apples are there now?`.
+    WordProblem { template: String, text: String },
+    /// Work out the change, e.g. `change from ¥20.00 for ¥12.50`.
+    MakeChange { price: Money, paid: Money },
+    /// Add up a shopping list of quantities and prices, e.g. `total of 2 x $1.25 + $3.40`.
+    ShoppingTotal { items: Vec<(i64, Money)> },
+    /// Pick the relation between the prices per item, e.g. `3 for ¥4.50 ☐ 5 for ¥7.00`.
+    CompareUnitPrices {
+        lhs: (i64, Money),
+        rhs: (i64, Money),
+    },
+    /// Find the time of day a duration in minutes after `start`, or before it if negative,
+    /// e.g. `what time is 2h 45m after 10:30`. Times are minutes after midnight.
+    ClockTime { start: i64, duration: i64 },
+    /// Count the minutes from `start` to `end`, e.g. `how many minutes between 8:15 and 11:05`.
+    MinutesBetween { start: i64, end: i64 },
+    /// Convert an amount to another unit of the same family, e.g. `3.2 km = ? m`.
+    ConvertUnits {
+        value: Decimal,
+        from: Unit,
+        to: Unit,
+    },
+}
+
+impl QuestionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuestionKind::Evaluate(_) => "evaluate",
+            QuestionKind::MissingOperand { .. } => "missing_operand",
+            QuestionKind::PercentOf { .. } => "percent_of",
+            QuestionKind::WhatPercent { .. } => "what_percent",
+            QuestionKind::SimplifyRatio { .. } => "simplify_ratio",
+            QuestionKind::Round { .. } => "round",
+            QuestionKind::Estimate(_) => "estimate",
+            QuestionKind::Gcd { .. } => "gcd",
+            QuestionKind::Lcm { .. } => "lcm",
+            QuestionKind::IsPrime(_) => "is_prime",
+            QuestionKind::Factorize(_) => "factorize",
+            QuestionKind::Equation { .. } => "equation",
+            QuestionKind::Sequence { .. } => "sequence",
+            QuestionKind::Compare { .. } => "compare",
+            QuestionKind::WordProblem { .. } => "word_problem",
+            QuestionKind::MakeChange { .. } => "make_change",
+            QuestionKind::ShoppingTotal { .. } => "shopping_total",
+            QuestionKind::CompareUnitPrices { .. } => "compare_unit_prices",
+            QuestionKind::ClockTime { .. } => "clock_time",
+            QuestionKind::MinutesBetween { .. } => "minutes_between",
+            QuestionKind::ConvertUnits { .. } => "convert_units",
+        }
+    }
+
+    /// The arithmetic expression of the question, `None` for kinds written out in words.
+    pub fn expression(&self) -> Option<&Expr> {
+        match self {
+            QuestionKind::Evaluate(expression) => Some(expression),
+            QuestionKind::MissingOperand { expression, .. } => Some(expression),
+            QuestionKind::Estimate(expression) => Some(expression),
+            _ => None,
+        }
+    }
+
+    /// Carries or borrows needed to work the question out exactly,
+    /// `None` for estimates and questions that aren't a single addition or subtraction.
+    pub fn regroupings(&self) -> Option<(Regrouping, u32)> {
+        match self {
+            QuestionKind::Evaluate(expression) => expression.regroupings(),
+            QuestionKind::MissingOperand { expression, .. } => expression.regroupings(),
+            _ => None,
+        }
+    }
+
+    /// What the units of a conversion measure, stored in the `unit_family` column,
+    /// `None` for other kinds.
+    pub fn unit_family(&self) -> Option<UnitFamily> {
+        match self {
+            QuestionKind::ConvertUnits { from, .. } => Some(from.family()),
+            _ => None,
+        }
+    }
+
+    /// Renders the question text, the unknown is marked with `?` unless it's the result.
+    pub fn render(&self, notation: Notation) -> String {
+        match self {
+            QuestionKind::Evaluate(expression) => expression.render(notation),
+            QuestionKind::MissingOperand { expression, blank } => format!(
+                "{} = {}",
+                expression.render_with_blank(notation, *blank),
+                expression
+                    .eval()
+                    .map(|value| value.to_string())
+                    .unwrap_or_default()
+            ),
+            QuestionKind::PercentOf { percent, whole } => match notation {
+                Notation::Latex => format!("{}\\% \\text{{ of }} {}", percent, whole),
+                _ => format!("{}% of {}", percent, whole),
+            },
+            QuestionKind::WhatPercent { part, whole } => match notation {
+                Notation::Latex => format!(
+                    "\\text{{what percent of }} {} \\text{{ is }} {}",
+                    whole, part
+                ),
+                _ => format!("what percent of {} is {}", whole, part),
+            },
+            QuestionKind::SimplifyRatio {
+                antecedent,
+                consequent,
+            } => match notation {
+                Notation::Latex => format!("\\text{{simplify }} {}:{}", antecedent, consequent),
+                _ => format!("simplify {}:{}", antecedent, consequent),
+            },
+            QuestionKind::Round { number, place } => {
+                let number = with_separators(*number);
+                // Profiles are validated against the named places, spell out any other
+                let place = place_name(*place)
+                    .map_or_else(|| with_separators(*place), |name| name.to_string());
+                match notation {
+                    Notation::Latex => format!(
+                        "\\text{{round }} {} \\text{{ to the nearest {}}}",
+                        number.replace(',', "{,}"),
+                        place.replace(',', "{,}")
+                    ),
+                    _ => format!("round {} to the nearest {}", number, place),
+                }
+            }
+            QuestionKind::Estimate(expression) => match notation {
+                Notation::Latex => format!("\\text{{estimate }} {}", expression.render(notation)),
+                _ => format!("estimate {}", expression.render(notation)),
+            },
+            QuestionKind::Gcd { a, b } => match notation {
+                Notation::Latex => format!("\\gcd({}, {})", a, b),
+                _ => format!("GCD of {} and {}", a, b),
+            },
+            QuestionKind::Lcm { a, b } => match notation {
+                Notation::Latex => format!("\\operatorname{{lcm}}({}, {})", a, b),
+                _ => format!("LCM of {} and {}", a, b),
+            },
+            QuestionKind::IsPrime(n) => match notation {
+                Notation::Latex => format!("\\text{{is }} {} \\text{{ prime}}", n),
+                _ => format!("is {} prime", n),
+            },
+            QuestionKind::Factorize(n) => match notation {
+                Notation::Latex => format!("\\text{{factor }} {} \\text{{ into primes}}", n),
+                _ => format!("factor {} into primes", n),
+            },
+            QuestionKind::Equation { lhs, rhs } => {
+                format!("{} = {}", lhs.render(notation), rhs.render(notation))
+            }
+            QuestionKind::Sequence { terms } => terms
+                .iter()
+                .map(|term| term.to_string())
+                .chain(["?".to_string()])
+                .collect::<Vec<_>>()
+                .join(", "),
+            QuestionKind::Compare { lhs, rhs } => {
+                let blank = match notation {
+                    Notation::Latex => "\\square",
+                    _ => "☐",
+                };
+                format!(
+                    "{} {} {}",
+                    lhs.render(notation),
+                    blank,
+                    rhs.render(notation)
+                )
+            }
+            QuestionKind::WordProblem { text, .. } => match notation {
+                Notation::Latex => format!("\\text{{{}}}", text),
+                _ => text.clone(),
+            },
+            QuestionKind::MakeChange { price, paid } => match notation {
+                Notation::Latex => format!(
+                    "\\text{{change from }} {} \\text{{ for }} {}",
+                    paid.render(notation),
+                    price.render(notation)
+                ),
+                _ => format!("change from {} for {}", paid, price),
+            },
+            QuestionKind::ShoppingTotal { items } => {
+                let times = match notation {
+                    Notation::Latex => " \\times ",
+                    _ => " x ",
+                };
+                let items = items
+                    .iter()
+                    .map(|(quantity, price)| match quantity {
+                        1 => price.render(notation),
+                        _ => format!("{}{}{}", quantity, times, price.render(notation)),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" + ");
+                match notation {
+                    Notation::Latex => format!("\\text{{total of }} {}", items),
+                    _ => format!("total of {}", items),
+                }
+            }
+            QuestionKind::CompareUnitPrices { lhs, rhs } => {
+                let (blank, of) = match notation {
+                    Notation::Latex => ("\\square", " \\text{ for } "),
+                    _ => ("☐", " for "),
+                };
+                format!(
+                    "{}{}{} {} {}{}{}",
+                    lhs.0,
+                    of,
+                    lhs.1.render(notation),
+                    blank,
+                    rhs.0,
+                    of,
+                    rhs.1.render(notation)
+                )
+            }
+            QuestionKind::ClockTime { start, duration } => {
+                let direction = if *duration < 0 { "before" } else { "after" };
+                let text = format!(
+                    "what time is {} {} {}",
+                    duration_text(duration.abs()),
+                    direction,
+                    clock(*start)
+                );
+                match notation {
+                    Notation::Latex => format!("\\text{{{}}}", text),
+                    _ => text,
+                }
+            }
+            QuestionKind::MinutesBetween { start, end } => {
+                let text = format!(
+                    "how many minutes between {} and {}",
+                    clock(*start),
+                    clock(*end)
+                );
+                match notation {
+                    Notation::Latex => format!("\\text{{{}}}", text),
+                    _ => text,
+                }
+            }
+            QuestionKind::ConvertUnits { value, from, to } => match notation {
+                Notation::Latex => format!(
+                    "{}\\,\\text{{{}}} = ?\\,\\text{{{}}}",
+                    value,
+                    from.symbol(),
+                    to.symbol()
+                ),
+                _ => format!("{} {} = ? {}", value, from.symbol(), to.symbol()),
+            },
+        }
+    }
+
+    /// Normalized form of an equation, stored in the `canonical` column so equivalent
+    /// equations can be told apart from their rendering, e.g. `3x - 18 = 0` for
+    /// `3x + 7 = 25`. `None` for other kinds.
+    pub fn canonical(&self) -> Option<String> {
+        let QuestionKind::Equation { lhs, rhs } = self else {
+            return None;
+        };
+        let ((a1, b1), (a2, b2)) = (lhs.linear()?, rhs.linear()?);
+        let (mut a, mut b) = (a1.checked_sub(a2)?, b1.checked_sub(b2)?);
+        let zero = Rational::from(0);
+        if a < zero {
+            (a, b) = (zero.checked_sub(a)?, zero.checked_sub(b)?);
+        }
+        let coefficient = match a.to_integer() {
+            Some(1) => String::new(),
+            _ => a.to_string(),
+        };
+        let constant = match b.cmp(&zero) {
+            Ordering::Less => format!(" - {}", zero.checked_sub(b)?),
+            Ordering::Equal => String::new(),
+            Ordering::Greater => format!(" + {}", b),
+        };
+        Some(format!("{}x{} = 0", coefficient, constant))
+    }
+
+    /// Splits the kind into the values of the `kind` and `expression` columns.
+    pub(crate) fn to_columns(&self) -> serde_json::Result<(&'static str, String)> {
+        let mut value = serde_json::to_value(self)?;
+        Ok((self.name(), serde_json::to_string(&value["body"].take())?))
+    }
+
+    /// The reverse of `to_columns`.
+    pub(crate) fn from_columns(kind: Option<&str>, body: &str) -> serde_json::Result<Self> {
+        serde_json::from_value(serde_json::json!({
+            "kind": kind.unwrap_or("evaluate"),
+            "body": serde_json::from_str::<serde_json::Value>(body)?,
+        }))
+    }
+}
+
+/// Formats a number with thousands separators, e.g. `4,587`.
+fn with_separators(number: i64) -> String {
+    let digits = number.unsigned_abs().to_string();
+    let mut result = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(digit);
+    }
+    if number < 0 {
+        result.insert(0, '-');
+    }
+    result
+}
+
+/// Formats minutes after midnight as a time of day, e.g. `8:15` or `13:05`.
+fn clock(minutes: i64) -> String {
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Formats a duration in minutes, e.g. `2h 45m`, `3h` or `40m`.
+fn duration_text(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h {}m", hours, minutes),
+    }
+}
+
+/// Name of a place in `RoundingProfile::PLACES`, `None` for any other place.
+fn place_name(place: i64) -> Option<&'static str> {
+    match place {
+        10 => Some("ten"),
+        100 => Some("hundred"),
+        1_000 => Some("thousand"),
+        10_000 => Some("ten thousand"),
+        100_000 => Some("hundred thousand"),
+        1_000_000 => Some("million"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_every_rounding_place() {
+        let round = |place| QuestionKind::Round {
+            number: 4_587_123,
+            place,
+        };
+        for (place, name) in [
+            (10, "ten"),
+            (100, "hundred"),
+            (1_000, "thousand"),
+            (10_000, "ten thousand"),
+            (100_000, "hundred thousand"),
+            (1_000_000, "million"),
+        ] {
+            assert_eq!(
+                round(place).render(Notation::Plain),
+                format!("round 4,587,123 to the nearest {}", name)
+            );
+        }
+        assert_eq!(
+            round(10_000_000).render(Notation::Plain),
+            "round 4,587,123 to the nearest 10,000,000"
+        );
+    }
+
+    #[test]
+    fn separates_thousands() {
+        assert_eq!(with_separators(0), "0");
+        assert_eq!(with_separators(999), "999");
+        assert_eq!(with_separators(4587), "4,587");
+        assert_eq!(with_separators(-123456), "-123,456");
+        assert_eq!(with_separators(i64::MIN), "-9,223,372,036,854,775,808");
+    }
+}
@@ -1,5 +1,38 @@
+mod answer;
+mod builtin;
+mod choices;
+mod decimal;
+mod expr;
+mod generator;
+mod grading;
+mod kind;
+mod migration;
+mod money;
+mod profile;
 mod question;
+mod rational;
+mod regrouping;
 mod repo;
+mod unit;
+mod word_problem;
 
+pub use answer::{Answer, Relation};
+pub use choices::{Choice, Choices, Misconception};
+pub use decimal::Decimal;
+pub use expr::{Expr, Notation, Operator};
+pub use generator::{question_rng, GeneratorRegistry, Parameter, ParameterType, QuestionGenerator};
+pub use grading::Grading;
+pub use kind::QuestionKind;
+pub use money::{Currency, Money};
+pub use profile::{
+    ComparisonProfile, DecimalProfile, DifficultyProfile, EquationProfile, EstimationProfile,
+    FractionProfile, MoneyProfile, MultiStepProfile, NumberTheoryProfile, OperationProfile,
+    PercentProfile, PowerProfile, RatioProfile, RoundingProfile, SequenceProfile, TimeProfile,
+    TimesTableProfile, UnitProfile, WordProblemProfile,
+};
 pub use question::Question;
+pub use rational::Rational;
+pub use regrouping::{Regrouping, RegroupingCount};
 pub use repo::QuizRepo;
+pub use unit::{Unit, UnitFamily};
+pub use word_problem::{Slot, WordProblemTemplate};
@@ -0,0 +1,272 @@
+//! The schema history of the questions database. The version of a database is kept in
+//! `PRAGMA user_version`, the number of migrations applied to it.
+//!
+//! Migrations are forward only: append new ones to `MIGRATIONS`, never edit, reorder or
+//! remove released ones.
+
+use log::{debug, info};
+use rusqlite::Connection;
+
+use crate::expr::Expr;
+
+struct Migration {
+    /// Logged when the migration is applied.
+    description: &'static str,
+    up: fn(&Connection) -> rusqlite::Result<()>,
+}
+
+/// Before versions were tracked, every start created the tables if they didn't exist and
+/// added missing columns, so databases at version 0 may already have any of the first
+/// migrations applied. Those are idempotent and simply catch such databases up.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "Create the questions table",
+        up: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS questions (
+                    id INTEGER PRIMARY KEY,
+                    question TEXT NOT NULL,
+                    expected_answer INTEGER NOT NULL,
+                    answer INTEGER,
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    answered_at TIMESTAMP
+                );
+                CREATE INDEX IF NOT EXISTS idx_created_at ON questions (created_at);",
+            )
+        },
+    },
+    Migration {
+        description: "Store the expression and the kind of questions",
+        up: |conn| {
+            add_column_if_missing(conn, "expression", "TEXT")?;
+            add_column_if_missing(conn, "kind", "TEXT")
+        },
+    },
+    Migration {
+        description: "Track carries and borrows, parse the expressions of old questions",
+        up: |conn| {
+            add_column_if_missing(conn, "regrouping", "TEXT")?;
+            add_column_if_missing(conn, "regroupings", "INTEGER")?;
+            backfill_expressions(conn)
+        },
+    },
+    Migration {
+        description: "Store the generator of questions",
+        up: |conn| add_column_if_missing(conn, "generator", "TEXT"),
+    },
+    Migration {
+        description: "Add seeded sessions",
+        up: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS sessions (
+                    id INTEGER PRIMARY KEY,
+                    seed INTEGER NOT NULL,
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+                );",
+            )?;
+            add_column_if_missing(conn, "session_id", "INTEGER")?;
+            add_column_if_missing(conn, "sequence", "INTEGER")
+        },
+    },
+    Migration {
+        description: "Store the grading policy and whether answers are correct",
+        up: |conn| {
+            add_column_if_missing(conn, "grading", "TEXT")?;
+            add_column_if_missing(conn, "correct", "INTEGER")?;
+            // Answers given before grading policies existed were graded exactly
+            conn.execute(
+                "UPDATE questions SET correct = (answer = expected_answer) WHERE answer IS NOT NULL AND correct IS NULL",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Store the canonical form of equations",
+        up: |conn| add_column_if_missing(conn, "canonical", "TEXT"),
+    },
+    Migration {
+        description: "Store the choices of multiple choice questions",
+        up: |conn| {
+            add_column_if_missing(conn, "choices", "TEXT")?;
+            add_column_if_missing(conn, "misconception", "TEXT")
+        },
+    },
+    Migration {
+        description: "Store the unit family of conversions",
+        up: |conn| add_column_if_missing(conn, "unit_family", "TEXT"),
+    },
+    Migration {
+        description: "Store the generator parameters questions were drawn with",
+        up: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE profiles (
+                    id INTEGER PRIMARY KEY,
+                    parameters TEXT NOT NULL UNIQUE
+                );
+                ALTER TABLE questions ADD COLUMN profile_id INTEGER;",
+            )
+        },
+    },
+];
+
+/// Applies the pending migrations in one transaction, so a failing migration leaves the
+/// database as it was. Databases written by a newer version are refused.
+pub(crate) fn migrate(conn: &mut Connection) -> anyhow::Result<()> {
+    let tx = conn.transaction()?;
+    let version: usize = tx.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version > MIGRATIONS.len() {
+        anyhow::bail!(
+            "The database is at version {}, newer than the latest known version {}",
+            version,
+            MIGRATIONS.len()
+        );
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        info!(
+            "Migrating the database to version {}: {}",
+            index + 1,
+            migration.description
+        );
+        (migration.up)(&tx)?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// `CREATE TABLE IF NOT EXISTS` leaves tables of existing databases untouched,
+/// so columns added later need to be added separately.
+fn add_column_if_missing(
+    conn: &Connection,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<()> {
+    let exists = conn
+        .prepare("SELECT 1 FROM pragma_table_info('questions') WHERE name = ?1")?
+        .exists([column])?;
+    if !exists {
+        debug!("Adding column {} to questions", column);
+        conn.execute_batch(&format!(
+            "ALTER TABLE questions ADD COLUMN {} {}",
+            column, definition
+        ))?;
+    }
+    Ok(())
+}
+
+/// Questions stored before the expression tree was introduced only have the text,
+/// parse it to fill in the `expression` and the regrouping columns.
+fn backfill_expressions(conn: &Connection) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare("SELECT id, question FROM questions WHERE expression IS NULL")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (id, question) in rows {
+        match question.parse::<Expr>() {
+            Ok(expression) => {
+                let regroupings = expression.regroupings();
+                conn.execute(
+                    "UPDATE questions SET expression = ?1, regrouping = ?2, regroupings = ?3 WHERE id = ?4",
+                    (
+                        &expression,
+                        regroupings.map(|(regrouping, _)| regrouping.name()),
+                        regroupings.map(|(_, count)| count),
+                        id,
+                    ),
+                )?;
+            }
+            Err(e) => log::warn!("Failed to parse question {}: {}", id, e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{answer::Answer, repo::QuizRepo};
+
+    fn version(conn: &Connection) -> usize {
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn migrates_baseline_databases() {
+        let path = std::env::temp_dir().join(format!("quiz-baseline-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let conn = Connection::open(&path).unwrap();
+        // The schema before any migrations, with integer answers
+        conn.execute_batch(
+            "CREATE TABLE questions (
+                id INTEGER PRIMARY KEY,
+                question TEXT NOT NULL,
+                expected_answer INTEGER NOT NULL,
+                answer INTEGER,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                answered_at TIMESTAMP
+            );
+            INSERT INTO questions (question, expected_answer, answer, answered_at) VALUES
+                ('12 + 39', 51, 51, datetime('now', '-1 day')),
+                ('7 x 8', 56, 54, datetime('now', '-1 day')),
+                ('40 - 13', 27, NULL, NULL);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let repo = QuizRepo::new(Some(&path)).await.unwrap();
+        assert_eq!(repo.get_statistics(None, None).await.unwrap(), (1, 2));
+        let mistakes = repo.mistake_collection().await.unwrap();
+        assert_eq!(mistakes.len(), 1);
+        assert_eq!(mistakes[0].to_string(), "7 x 8 = 54");
+        assert_eq!(mistakes[0].get_expected_answer(), Answer::Integer(56));
+        drop(repo);
+
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(version(&conn), MIGRATIONS.len());
+        let regrouping: String = conn
+            .query_row(
+                "SELECT regrouping FROM questions WHERE question = '12 + 39'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(regrouping, "carry");
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn migrating_again_changes_nothing() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO questions (question, expected_answer) VALUES ('1 + 1', 2)",
+            [],
+        )
+        .unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(version(&conn), MIGRATIONS.len());
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM questions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn refuses_newer_databases() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+        let e = migrate(&mut conn).unwrap_err();
+        assert!(e.to_string().contains("newer"), "{}", e);
+        assert_eq!(version(&conn), MIGRATIONS.len() + 1);
+        let tables: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tables, 0);
+    }
+}
@@ -0,0 +1,189 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{decimal::Decimal, expr::Notation, rational::Rational};
+
+/// The currency amounts are shown in, both have 100 minor units.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Currency {
+    /// Yuan and fen, e.g. `¥12.50`.
+    #[default]
+    Cny,
+    /// Dollars and cents, e.g. `$3.05`.
+    Usd,
+}
+
+impl Currency {
+    /// Yuan for Chinese locales like `zh` or `zh-CN`, dollars otherwise.
+    pub fn from_locale(locale: &str) -> Self {
+        if locale.starts_with("zh") {
+            Currency::Cny
+        } else {
+            Currency::Usd
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Currency::Cny => "cny",
+            Currency::Usd => "usd",
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Currency::Cny => "¥",
+            Currency::Usd => "$",
+        }
+    }
+}
+
+/// An amount of money in minor units, e.g. `1250` fen is `¥12.50`.
+/// Keeping whole minor units avoids rounding errors when adding prices up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Money {
+    pub minor: i64,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(minor: i64, currency: Currency) -> Self {
+        Self { minor, currency }
+    }
+
+    /// Parses an amount in the currency, e.g. `12.5`, `¥12.50`, `12.50元` or `$3`.
+    /// The currency symbol is optional, at most 2 decimal places are allowed.
+    pub fn parse(input: &str, currency: Currency) -> anyhow::Result<Self> {
+        let mut amount = input.trim();
+        let (negative, rest) = match amount.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, amount),
+        };
+        amount = rest.trim_start();
+        amount = match currency {
+            Currency::Cny => amount
+                .trim_start_matches(['¥', '￥'])
+                .trim_end_matches('元'),
+            Currency::Usd => amount.trim_start_matches('$'),
+        }
+        .trim();
+        let invalid = || anyhow::Error::msg(format!("Invalid amount of money: {}", input));
+        let minor = amount
+            .parse::<Decimal>()
+            .map_err(|_| invalid())?
+            .to_rational()
+            .checked_mul(Rational::from(100))
+            .and_then(|minor| minor.to_integer())
+            .ok_or_else(invalid)?;
+        Ok(Self::new(if negative { -minor } else { minor }, currency))
+    }
+
+    pub fn to_rational(&self) -> Rational {
+        Rational::new(self.minor, 100).unwrap()
+    }
+
+    /// The amount with its currency symbol, LaTeX escapes the `$`.
+    pub fn render(&self, notation: Notation) -> String {
+        let text = self.to_string();
+        match (notation, self.currency) {
+            (Notation::Latex, Currency::Cny) => text.replace('¥', "\\yen "),
+            (Notation::Latex, Currency::Usd) => text.replace('$', "\\$"),
+            _ => text,
+        }
+    }
+}
+
+/// Always shows both decimal places, e.g. `¥12.50`, `$3.05` or `-$0.40`.
+impl Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let minor = self.minor.unsigned_abs();
+        write!(
+            f,
+            "{}{}{}.{:02}",
+            sign,
+            self.currency.symbol(),
+            minor / 100,
+            minor % 100
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_with_or_without_symbols() {
+        let cases = [
+            ("12.5", Currency::Cny, 1250),
+            ("¥12.50", Currency::Cny, 1250),
+            ("￥12.50", Currency::Cny, 1250),
+            ("12.50元", Currency::Cny, 1250),
+            (" ¥ 7 元 ", Currency::Cny, 700),
+            ("$3.05", Currency::Usd, 305),
+            ("3", Currency::Usd, 300),
+            ("0.4", Currency::Usd, 40),
+            ("-$0.40", Currency::Usd, -40),
+            ("- 2.5", Currency::Cny, -250),
+        ];
+        for (input, currency, minor) in cases {
+            assert_eq!(
+                Money::parse(input, currency).unwrap(),
+                Money::new(minor, currency),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_amounts() {
+        for input in ["", "¥", "abc", "1.005", "12.345", "$3"] {
+            assert!(Money::parse(input, Currency::Cny).is_err(), "{}", input);
+        }
+        assert!(Money::parse("3元", Currency::Usd).is_err());
+    }
+
+    #[test]
+    fn displays_both_decimal_places() {
+        assert_eq!(Money::new(1250, Currency::Cny).to_string(), "¥12.50");
+        assert_eq!(Money::new(305, Currency::Usd).to_string(), "$3.05");
+        assert_eq!(Money::new(-40, Currency::Usd).to_string(), "-$0.40");
+        assert_eq!(Money::new(0, Currency::Cny).to_string(), "¥0.00");
+        for minor in [-40, 0, 5, 1250, 123456] {
+            for currency in [Currency::Cny, Currency::Usd] {
+                let money = Money::new(minor, currency);
+                assert_eq!(Money::parse(&money.to_string(), currency).unwrap(), money);
+            }
+        }
+    }
+
+    #[test]
+    fn renders_for_latex() {
+        assert_eq!(
+            Money::new(1250, Currency::Cny).render(Notation::Latex),
+            "\\yen 12.50"
+        );
+        assert_eq!(
+            Money::new(-305, Currency::Usd).render(Notation::Latex),
+            "-\\$3.05"
+        );
+        assert_eq!(
+            Money::new(305, Currency::Usd).render(Notation::Plain),
+            "$3.05"
+        );
+    }
+
+    #[test]
+    fn currency_of_locale() {
+        assert_eq!(Currency::from_locale("zh-CN"), Currency::Cny);
+        assert_eq!(Currency::from_locale("en"), Currency::Usd);
+        assert_eq!(
+            Money::new(1250, Currency::Cny).to_rational(),
+            Rational::new(25, 2).unwrap()
+        );
+    }
+}
@@ -0,0 +1,1314 @@
+use std::{fmt::Debug, ops::RangeInclusive};
+
+use crate::{money::Currency, regrouping::RegroupingCount, word_problem::WordProblemTemplate};
+
+/// Selection weight and operand range of one arithmetic operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OperationProfile {
+    /// Relative chance of picking this operation, 0 disables it.
+    pub weight: u32,
+    /// Range the operands are drawn from, must not be empty.
+    /// For division this is the range of both the divisor and the quotient.
+    pub operands: RangeInclusive<i64>,
+}
+
+impl OperationProfile {
+    pub fn new(weight: u32, operands: RangeInclusive<i64>) -> Self {
+        Self { weight, operands }
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(0, 0..=0)
+    }
+
+    fn validate(&self, name: &str) -> anyhow::Result<()> {
+        check_range(&format!("{} operands", name), &self.operands, None)
+    }
+}
+
+/// Expressions with several operations, e.g. `(35 + 17) x 4 - 12`.
+/// The operators are mixed according to the weights of the single operations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiStepProfile {
+    /// Relative chance of a multi-step expression, 0 disables them.
+    pub weight: u32,
+    /// Number of operands in an expression, 2 is a single operation.
+    pub terms: RangeInclusive<usize>,
+    /// Range the operands are drawn from, must not be empty.
+    pub operands: RangeInclusive<i64>,
+    /// Upper bound of the answer and every intermediate result.
+    pub max_value: i64,
+}
+
+impl MultiStepProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            terms: 3..=4,
+            operands: 2..=50,
+            max_value: 1000,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        check_range("multi-step terms", &self.terms, None)?;
+        check_range("multi-step operands", &self.operands, None)
+    }
+}
+
+/// Fraction arithmetic with proper fractions, e.g. `3/4 + 1/6`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FractionProfile {
+    /// Relative chance of a fraction question, 0 disables them.
+    pub weight: u32,
+    /// Range of the denominators, must start above 1.
+    pub denominators: RangeInclusive<i64>,
+    /// Allow operands with different denominators.
+    pub unlike_denominators: bool,
+    /// Allow multiplication and division besides addition and subtraction.
+    pub multiply_divide: bool,
+}
+
+impl FractionProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            denominators: 2..=12,
+            unlike_denominators: false,
+            multiply_divide: false,
+        }
+    }
+
+    /// A denominator of 1 has no proper fractions.
+    fn validate(&self) -> anyhow::Result<()> {
+        check_range("fraction denominators", &self.denominators, Some(2))
+    }
+}
+
+/// Decimal arithmetic, e.g. `3.75 + 12.4` or `0.6 x 0.25`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecimalProfile {
+    /// Relative chance of a decimal question, 0 disables them.
+    pub weight: u32,
+    /// Range of the whole part of the operands, must not be empty or negative.
+    pub whole: RangeInclusive<i64>,
+    /// Maximum number of decimal places of the operands, at least 1.
+    pub places: u32,
+    /// Allow multiplication besides addition and subtraction,
+    /// the whole part of the factors is kept below 10, so `whole` must start below 10.
+    pub multiply: bool,
+}
+
+impl DecimalProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            whole: 0..=20,
+            places: 2,
+            multiply: false,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        check_range("decimal whole parts", &self.whole, Some(0))?;
+        if self.places < 1 {
+            anyhow::bail!("Decimals need at least 1 place");
+        }
+        if 10i64
+            .checked_pow(self.places)
+            .and_then(|unit| unit.checked_mul(self.whole.end().checked_add(1)?))
+            .is_none()
+        {
+            anyhow::bail!("The decimal operands are too large");
+        }
+        if self.multiply && *self.whole.start() > 9 {
+            anyhow::bail!(
+                "Invalid decimal whole parts {:?}, factors need whole parts below 10",
+                self.whole
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Multiplication and division facts of selected times tables, e.g. `7 x 8` or `56 ÷ 7`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimesTableProfile {
+    /// Relative chance of a times table question, 0 disables them.
+    pub weight: u32,
+    /// The tables to drill, e.g. `[6, 7, 8]`, must not be empty or contain 0.
+    pub tables: Vec<i64>,
+    /// Largest factor the tables go up to, e.g. 12 for `7 x 12`, the smallest is 2.
+    pub max_factor: i64,
+    /// Allow division facts besides multiplication.
+    pub divide: bool,
+}
+
+impl TimesTableProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            tables: (2..=9).collect(),
+            max_factor: 9,
+            divide: false,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.tables.is_empty() || self.tables.contains(&0) {
+            anyhow::bail!(
+                "Invalid times tables {:?}, they must not be empty or contain 0",
+                self.tables
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Powers and roots, e.g. `7²`, `2⁵`, `√144` or `∛27`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PowerProfile {
+    /// Relative chance of a power or root question, 0 disables them.
+    pub weight: u32,
+    /// Range of the bases of powers and of the values of roots, must not be empty or
+    /// negative. The smallest base to the largest exponent must be within `max_value`.
+    pub bases: RangeInclusive<i64>,
+    /// Largest exponent, e.g. 5 for `2⁵`, the smallest is 2.
+    pub max_exponent: u32,
+    /// Upper bound of the powers and of the numbers under a root.
+    pub max_value: i64,
+    /// Allow square roots of perfect squares.
+    pub square_roots: bool,
+    /// Allow cube roots of perfect cubes.
+    pub cube_roots: bool,
+}
+
+impl PowerProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            bases: 1..=12,
+            max_exponent: 2,
+            max_value: 144,
+            square_roots: false,
+            cube_roots: false,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        check_range("power bases", &self.bases, Some(0))?;
+        // Cube roots are cubes backwards, even if the powers stop at squares
+        let exponent = self.max_exponent.max(if self.cube_roots { 3 } else { 2 });
+        if !self
+            .bases
+            .start()
+            .checked_pow(exponent)
+            .is_some_and(|value| value <= self.max_value)
+        {
+            anyhow::bail!(
+                "Invalid power bases {:?}, {} to the power of {} is above the largest value {}",
+                self.bases,
+                self.bases.start(),
+                exponent,
+                self.max_value
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Percentages, e.g. `25% of 80` or `what percent of 60 is 15`.
+/// The percentages are multiples of 5 below 100.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PercentProfile {
+    /// Relative chance of a percentage question, 0 disables them.
+    pub weight: u32,
+    /// Range of the number the percentage is taken of, must not be empty.
+    pub wholes: RangeInclusive<i64>,
+    /// Allow asking for the percentage itself, e.g. `what percent of 60 is 15`.
+    pub what_percent: bool,
+}
+
+impl PercentProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            wholes: 10..=200,
+            what_percent: false,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        check_range("percent wholes", &self.wholes, None)
+    }
+}
+
+/// Simplifying ratios, e.g. `simplify 12:18`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RatioProfile {
+    /// Relative chance of a ratio question, 0 disables them.
+    pub weight: u32,
+    /// Range of the terms of the simplified ratio, must start above 0.
+    pub terms: RangeInclusive<i64>,
+    /// Largest common factor of the terms of the question, the smallest is 2.
+    pub max_factor: i64,
+}
+
+impl RatioProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            terms: 1..=10,
+            max_factor: 10,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        check_range("ratio terms", &self.terms, Some(1))
+    }
+}
+
+/// Rounding to a power of ten, e.g. `round 4,587 to the nearest hundred`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundingProfile {
+    /// Relative chance of a rounding question, 0 disables them.
+    pub weight: u32,
+    /// Range of the numbers to round, must not be empty.
+    pub numbers: RangeInclusive<i64>,
+    /// Range of the exponents of the place to round to, e.g. `1..=2` for tens and hundreds.
+    pub places: RangeInclusive<u32>,
+}
+
+impl RoundingProfile {
+    /// The places that have a name, from tens to millions.
+    pub const PLACES: RangeInclusive<u32> = 1..=6;
+
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            numbers: 10..=999,
+            places: 1..=2,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        check_range("rounding numbers", &self.numbers, None)?;
+        if self.places.is_empty()
+            || !Self::PLACES.contains(self.places.start())
+            || !Self::PLACES.contains(self.places.end())
+        {
+            anyhow::bail!(
+                "Invalid rounding places {:?}, only tens to millions ({:?}) have a name",
+                self.places,
+                Self::PLACES
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Estimating sums and products, e.g. `estimate 398 x 21`. The expected answer rounds
+/// every operand to its leading digit, `400 x 20 = 8000`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EstimationProfile {
+    /// Relative chance of an estimation question, 0 disables them.
+    pub weight: u32,
+    /// Range of the operands, must start above 10.
+    pub operands: RangeInclusive<i64>,
+    /// Accepted distance from the expected answer in percent. Answers between the
+    /// expected answer and the exact value are always accepted.
+    pub tolerance: u32,
+}
+
+impl EstimationProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            operands: 11..=999,
+            tolerance: 10,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        check_range("estimation operands", &self.operands, Some(11))
+    }
+}
+
+/// Number theory, e.g. `GCD of 12 and 18`, `LCM of 4 and 6`, `is 91 prime` or
+/// `factor 84 into primes`. If every question type is disabled, GCD is asked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NumberTheoryProfile {
+    /// Relative chance of a number theory question, 0 disables them.
+    pub weight: u32,
+    /// Range of the numbers in the questions, must start at 2 or above.
+    pub numbers: RangeInclusive<i64>,
+    /// Largest least common multiple to ask for.
+    pub max_lcm: i64,
+    pub gcd: bool,
+    pub lcm: bool,
+    pub primes: bool,
+    pub factorization: bool,
+}
+
+impl NumberTheoryProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            numbers: 2..=100,
+            max_lcm: 200,
+            gcd: true,
+            lcm: true,
+            primes: true,
+            factorization: true,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        check_range("number theory numbers", &self.numbers, Some(2))
+    }
+}
+
+/// Linear equations with an integer solution, e.g. `3x + 7 = 25` or `2(x - 4) = 10`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EquationProfile {
+    /// Relative chance of an equation, 0 disables them.
+    pub weight: u32,
+    /// Range of the solutions, must not be empty.
+    pub solutions: RangeInclusive<i64>,
+    /// Range of the coefficients of `x`, must start above 0.
+    pub coefficients: RangeInclusive<i64>,
+    /// Range of the constants added to or subtracted from `x`, must not be empty.
+    pub constants: RangeInclusive<i64>,
+    /// Allow equations like `2(x - 4) = 10`.
+    pub brackets: bool,
+}
+
+impl EquationProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            solutions: 1..=20,
+            coefficients: 2..=9,
+            constants: 1..=50,
+            brackets: false,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        check_range("equation solutions", &self.solutions, None)?;
+        check_range("equation coefficients", &self.coefficients, Some(1))?;
+        check_range("equation constants", &self.constants, None)
+    }
+}
+
+/// Arithmetic and geometric sequences to continue, e.g. `3, 7, 11, 15, ?` or `2, 6, 18, ?`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SequenceProfile {
+    /// Relative chance of a sequence question, 0 disables them.
+    pub weight: u32,
+    /// Range of the number of terms shown before the `?`, must start at 3 or above.
+    pub terms: RangeInclusive<usize>,
+    /// Range of the first term of an increasing sequence, must not be empty.
+    pub starts: RangeInclusive<i64>,
+    /// Range of the differences of arithmetic sequences, must start above 0.
+    pub steps: RangeInclusive<i64>,
+    /// Allow geometric sequences, e.g. `2, 6, 18, ?`.
+    pub geometric: bool,
+    /// Range of the ratios of geometric sequences, must start at 2 or above.
+    pub ratios: RangeInclusive<i64>,
+    /// Allow sequences counting down, e.g. `54, 18, 6, ?`.
+    pub decreasing: bool,
+    /// Largest term of a geometric sequence.
+    pub max_value: i64,
+}
+
+impl SequenceProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            terms: 4..=5,
+            starts: 1..=20,
+            steps: 2..=10,
+            geometric: false,
+            ratios: 2..=3,
+            decreasing: false,
+            max_value: 1000,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        check_range("sequence terms", &self.terms, Some(3))?;
+        check_range("sequence starts", &self.starts, None)?;
+        check_range("sequence steps", &self.steps, Some(1))?;
+        // The answer follows the shown terms, it's the last and largest one
+        let count = *self.terms.end();
+        if i64::try_from(count)
+            .ok()
+            .and_then(|count| count.checked_mul(*self.steps.end()))
+            .and_then(|growth| growth.checked_add(*self.starts.end()))
+            .is_none()
+        {
+            anyhow::bail!("The terms of arithmetic sequences are too large");
+        }
+        if self.geometric {
+            check_range("sequence ratios", &self.ratios, Some(2))?;
+            if u32::try_from(count)
+                .ok()
+                .and_then(|count| self.ratios.end().checked_pow(count))
+                .is_none()
+            {
+                anyhow::bail!("The terms of geometric sequences are too large");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Comparing two expressions, e.g. `38 + 47 ☐ 9 x 9`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComparisonProfile {
+    /// Relative chance of a comparison question, 0 disables them.
+    pub weight: u32,
+    /// Range the operands of additions and subtractions are drawn from, must start above 0.
+    pub operands: RangeInclusive<i64>,
+    /// Allow products of one digit numbers, e.g. `9 x 9`.
+    pub multiply: bool,
+}
+
+impl ComparisonProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            operands: 1..=20,
+            multiply: false,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        check_range("comparison operands", &self.operands, Some(1))
+    }
+}
+
+/// Word problems filled in from templates, e.g. `Mia has 12 apples and buys 7 more`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WordProblemProfile {
+    /// Relative chance of a word problem, 0 or no templates disable them.
+    pub weight: u32,
+    /// Picked with equal chance, see `WordProblemTemplate::load`.
+    pub templates: Vec<WordProblemTemplate>,
+    /// Language of the text, e.g. `en` or `zh`, templates without it fall back to English.
+    pub locale: String,
+}
+
+impl WordProblemProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            templates: vec![],
+            locale: "en".to_string(),
+        }
+    }
+
+    /// The built-in templates suiting the school grade.
+    pub fn grade(grade: u8) -> Self {
+        Self {
+            weight: 1,
+            templates: WordProblemTemplate::builtin()
+                .into_iter()
+                .filter(|template| template.suits(grade))
+                .collect(),
+            locale: "en".to_string(),
+        }
+    }
+}
+
+/// Money questions: making change, totaling a shopping list and comparing unit prices.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MoneyProfile {
+    /// Relative chance of a money question, 0 disables them.
+    pub weight: u32,
+    /// Currency the amounts are shown in, see `Currency::from_locale`.
+    pub currency: Currency,
+    /// Range of the prices in minor units, e.g. `50..=2000` for `¥0.50` to `¥20.00`,
+    /// must start at `step` or above and hold a multiple of it.
+    pub prices: RangeInclusive<i64>,
+    /// Prices are multiples of this many minor units, e.g. 10 for whole jiao or dimes,
+    /// must be above 0.
+    pub step: i64,
+    /// Range of the number of items on a shopping list, must start at 2 or above.
+    pub items: RangeInclusive<usize>,
+    /// Allow comparing prices per item, e.g. `3 for ¥4.50 ☐ 5 for ¥7.00`.
+    pub unit_prices: bool,
+}
+
+impl MoneyProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            currency: Currency::default(),
+            prices: 100..=2000,
+            step: 10,
+            items: 2..=3,
+            unit_prices: false,
+        }
+    }
+
+    /// Range of the prices per item when comparing unit prices,
+    /// so packs of up to 6 stay within the price range.
+    pub(crate) fn unit_price_range(&self) -> RangeInclusive<i64> {
+        let start = *self.prices.start();
+        start..=(self.prices.end() / 6).max(start)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.step < 1 {
+            anyhow::bail!("Invalid money step {}, it must be above 0", self.step);
+        }
+        check_range("money prices", &self.prices, Some(self.step))?;
+        check_range("money items", &self.items, Some(2))?;
+        let mut ranges = vec![("prices", self.prices.clone())];
+        if self.unit_prices {
+            ranges.push(("unit prices", self.unit_price_range()));
+        }
+        for (name, prices) in ranges {
+            // The largest multiple of the step up to the end
+            if prices.end() / self.step * self.step < *prices.start() {
+                anyhow::bail!(
+                    "Invalid money {} {:?}, no price is a multiple of {}",
+                    name,
+                    prices,
+                    self.step
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Clock arithmetic, e.g. `what time is 2h 45m after 10:30` or
+/// `how many minutes between 8:15 and 11:05`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeProfile {
+    /// Relative chance of a time question, 0 disables them.
+    pub weight: u32,
+    /// Times and durations are multiples of this many minutes, must be between 1 and 60.
+    pub step: i64,
+    /// Longest duration in minutes, must be at least `step`.
+    pub max_duration: i64,
+    /// Allow going back in time, e.g. `what time is 1h 20m before 9:10`.
+    pub before: bool,
+}
+
+impl TimeProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            step: 5,
+            max_duration: 120,
+            before: false,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !(1..=60).contains(&self.step) || self.max_duration < self.step {
+            anyhow::bail!(
+                "Invalid time step {} and longest duration {}, the step must be between 1 and 60 and the duration at least the step",
+                self.step,
+                self.max_duration
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Converting between units of length, mass and volume, e.g. `3.2 km = ? m`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnitProfile {
+    /// Relative chance of a conversion question, 0 disables them.
+    pub weight: u32,
+    /// Allow units of length, e.g. `cm` and `m`.
+    pub length: bool,
+    /// Allow units of mass, e.g. `g` and `kg`.
+    pub mass: bool,
+    /// Allow units of volume, e.g. `mL` and `L`.
+    pub volume: bool,
+    /// Allow amounts with decimals, e.g. `4.25 kg`, metric units only.
+    pub decimals: bool,
+    /// Add imperial units besides the metric ones, e.g. `ft` and `in`.
+    pub imperial: bool,
+}
+
+impl UnitProfile {
+    pub fn disabled() -> Self {
+        Self {
+            weight: 0,
+            length: true,
+            mass: false,
+            volume: false,
+            decimals: false,
+            imperial: false,
+        }
+    }
+}
+
+/// Controls which operations `generate_question` picks and how large the operands are.
+/// If every operation is disabled, addition is used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DifficultyProfile {
+    pub addition: OperationProfile,
+    pub subtraction: OperationProfile,
+    pub multiplication: OperationProfile,
+    pub division: OperationProfile,
+    pub multi_step: MultiStepProfile,
+    pub fraction: FractionProfile,
+    pub decimal: DecimalProfile,
+    /// Division with remainder, the operands are the divisor and the quotient.
+    pub remainder: OperationProfile,
+    /// Relative chance of a question like `? + 37 = 120`, built from one of the single
+    /// operations above, 0 disables them.
+    pub missing_operand: u32,
+    /// Give the operands of the single operations random signs, e.g. `-15 + 8` or
+    /// `-6 x (-4)`, and let subtraction go below zero, e.g. `7 - 23`.
+    pub negative_numbers: bool,
+    /// Number of carries in addition, e.g. `RegroupingCount::None` for `23 + 45`,
+    /// `None` doesn't constrain them.
+    pub carries: Option<RegroupingCount>,
+    /// Number of borrows in subtraction, e.g. `RegroupingCount::One` for `52 - 27`,
+    /// `None` doesn't constrain them.
+    pub borrows: Option<RegroupingCount>,
+    pub times_table: TimesTableProfile,
+    pub power: PowerProfile,
+    pub percent: PercentProfile,
+    pub ratio: RatioProfile,
+    pub rounding: RoundingProfile,
+    pub estimation: EstimationProfile,
+    pub number_theory: NumberTheoryProfile,
+    pub equation: EquationProfile,
+    pub sequence: SequenceProfile,
+    pub comparison: ComparisonProfile,
+    pub word_problem: WordProblemProfile,
+    pub money: MoneyProfile,
+    pub time: TimeProfile,
+    pub unit: UnitProfile,
+}
+
+impl DifficultyProfile {
+    /// Preset profile for the given school grade, grades outside 1..=6 are clamped.
+    pub fn grade(grade: u8) -> Self {
+        match grade.clamp(1, 6) {
+            1 => Self {
+                addition: OperationProfile::new(1, 1..=20),
+                subtraction: OperationProfile::new(1, 1..=20),
+                multiplication: OperationProfile::disabled(),
+                division: OperationProfile::disabled(),
+                multi_step: MultiStepProfile::disabled(),
+                fraction: FractionProfile::disabled(),
+                decimal: DecimalProfile::disabled(),
+                remainder: OperationProfile::disabled(),
+                missing_operand: 1,
+                negative_numbers: false,
+                carries: None,
+                borrows: None,
+                times_table: TimesTableProfile::disabled(),
+                power: PowerProfile::disabled(),
+                percent: PercentProfile::disabled(),
+                ratio: RatioProfile::disabled(),
+                rounding: RoundingProfile::disabled(),
+                estimation: EstimationProfile::disabled(),
+                number_theory: NumberTheoryProfile::disabled(),
+                equation: EquationProfile::disabled(),
+                sequence: SequenceProfile::disabled(),
+                comparison: ComparisonProfile {
+                    weight: 1,
+                    operands: 1..=10,
+                    multiply: false,
+                },
+                word_problem: WordProblemProfile::grade(1),
+                money: MoneyProfile {
+                    weight: 1,
+                    currency: Currency::default(),
+                    prices: 100..=2000,
+                    step: 100,
+                    items: 2..=2,
+                    unit_prices: false,
+                },
+                time: TimeProfile::disabled(),
+                unit: UnitProfile::disabled(),
+            },
+            2 => Self {
+                addition: OperationProfile::new(2, 1..=100),
+                subtraction: OperationProfile::new(2, 1..=100),
+                multiplication: OperationProfile::new(1, 2..=9),
+                division: OperationProfile::disabled(),
+                multi_step: MultiStepProfile::disabled(),
+                fraction: FractionProfile::disabled(),
+                decimal: DecimalProfile::disabled(),
+                remainder: OperationProfile::disabled(),
+                missing_operand: 1,
+                negative_numbers: false,
+                carries: None,
+                borrows: None,
+                times_table: TimesTableProfile::disabled(),
+                power: PowerProfile::disabled(),
+                percent: PercentProfile::disabled(),
+                ratio: RatioProfile::disabled(),
+                rounding: RoundingProfile::disabled(),
+                estimation: EstimationProfile::disabled(),
+                number_theory: NumberTheoryProfile::disabled(),
+                equation: EquationProfile::disabled(),
+                sequence: SequenceProfile {
+                    weight: 1,
+                    ..SequenceProfile::disabled()
+                },
+                comparison: ComparisonProfile {
+                    weight: 1,
+                    operands: 1..=50,
+                    multiply: true,
+                },
+                word_problem: WordProblemProfile::grade(2),
+                money: MoneyProfile {
+                    weight: 1,
+                    currency: Currency::default(),
+                    prices: 10..=5000,
+                    step: 10,
+                    items: 2..=3,
+                    unit_prices: false,
+                },
+                time: TimeProfile {
+                    weight: 1,
+                    step: 5,
+                    max_duration: 120,
+                    before: false,
+                },
+                unit: UnitProfile {
+                    weight: 1,
+                    length: true,
+                    mass: false,
+                    volume: false,
+                    decimals: false,
+                    imperial: false,
+                },
+            },
+            3 => Self {
+                addition: OperationProfile::new(2, 2..=1000),
+                subtraction: OperationProfile::new(2, 2..=1000),
+                multiplication: OperationProfile::new(2, 2..=9),
+                division: OperationProfile::new(1, 2..=9),
+                multi_step: MultiStepProfile::disabled(),
+                fraction: FractionProfile::disabled(),
+                decimal: DecimalProfile::disabled(),
+                remainder: OperationProfile::new(1, 2..=9),
+                missing_operand: 1,
+                negative_numbers: false,
+                carries: None,
+                borrows: None,
+                times_table: TimesTableProfile::disabled(),
+                power: PowerProfile::disabled(),
+                percent: PercentProfile::disabled(),
+                ratio: RatioProfile::disabled(),
+                rounding: RoundingProfile {
+                    weight: 1,
+                    numbers: 10..=9999,
+                    places: 1..=2,
+                },
+                estimation: EstimationProfile {
+                    weight: 1,
+                    ..EstimationProfile::disabled()
+                },
+                number_theory: NumberTheoryProfile::disabled(),
+                equation: EquationProfile::disabled(),
+                sequence: SequenceProfile {
+                    weight: 1,
+                    decreasing: true,
+                    ..SequenceProfile::disabled()
+                },
+                comparison: ComparisonProfile {
+                    weight: 1,
+                    operands: 10..=500,
+                    multiply: true,
+                },
+                word_problem: WordProblemProfile::grade(3),
+                money: MoneyProfile {
+                    weight: 1,
+                    currency: Currency::default(),
+                    prices: 5..=10000,
+                    step: 5,
+                    items: 2..=4,
+                    unit_prices: true,
+                },
+                time: TimeProfile {
+                    weight: 1,
+                    step: 5,
+                    max_duration: 300,
+                    before: true,
+                },
+                unit: UnitProfile {
+                    weight: 1,
+                    length: true,
+                    mass: true,
+                    volume: false,
+                    decimals: false,
+                    imperial: false,
+                },
+            },
+            4 => Self {
+                addition: OperationProfile::new(2, 2..=1000),
+                subtraction: OperationProfile::new(2, 2..=1000),
+                multiplication: OperationProfile::new(2, 2..=100),
+                division: OperationProfile::new(1, 9..=50),
+                multi_step: MultiStepProfile::disabled(),
+                fraction: FractionProfile {
+                    weight: 1,
+                    ..FractionProfile::disabled()
+                },
+                decimal: DecimalProfile {
+                    weight: 1,
+                    ..DecimalProfile::disabled()
+                },
+                remainder: OperationProfile::new(1, 2..=12),
+                missing_operand: 1,
+                negative_numbers: false,
+                carries: None,
+                borrows: None,
+                times_table: TimesTableProfile::disabled(),
+                power: PowerProfile::disabled(),
+                percent: PercentProfile::disabled(),
+                ratio: RatioProfile::disabled(),
+                rounding: RoundingProfile {
+                    weight: 1,
+                    numbers: 100..=99999,
+                    places: 1..=3,
+                },
+                estimation: EstimationProfile {
+                    weight: 1,
+                    ..EstimationProfile::disabled()
+                },
+                number_theory: NumberTheoryProfile::disabled(),
+                equation: EquationProfile::disabled(),
+                sequence: SequenceProfile {
+                    weight: 1,
+                    decreasing: true,
+                    ..SequenceProfile::disabled()
+                },
+                comparison: ComparisonProfile::disabled(),
+                word_problem: WordProblemProfile::grade(4),
+                money: MoneyProfile {
+                    weight: 1,
+                    currency: Currency::default(),
+                    prices: 1..=20000,
+                    step: 1,
+                    items: 2..=4,
+                    unit_prices: true,
+                },
+                time: TimeProfile {
+                    weight: 1,
+                    step: 1,
+                    max_duration: 600,
+                    before: true,
+                },
+                unit: UnitProfile {
+                    weight: 1,
+                    length: true,
+                    mass: true,
+                    volume: true,
+                    decimals: true,
+                    imperial: false,
+                },
+            },
+            5 => Self {
+                addition: OperationProfile::new(1, 100..=10000),
+                subtraction: OperationProfile::new(1, 100..=10000),
+                multiplication: OperationProfile::new(2, 10..=200),
+                division: OperationProfile::new(2, 9..=99),
+                multi_step: MultiStepProfile {
+                    weight: 1,
+                    ..MultiStepProfile::disabled()
+                },
+                fraction: FractionProfile {
+                    weight: 2,
+                    denominators: 2..=12,
+                    unlike_denominators: true,
+                    multiply_divide: false,
+                },
+                decimal: DecimalProfile {
+                    weight: 2,
+                    whole: 0..=100,
+                    places: 2,
+                    multiply: true,
+                },
+                remainder: OperationProfile::new(1, 9..=50),
+                missing_operand: 1,
+                negative_numbers: false,
+                carries: None,
+                borrows: None,
+                times_table: TimesTableProfile::disabled(),
+                power: PowerProfile::disabled(),
+                percent: PercentProfile::disabled(),
+                ratio: RatioProfile::disabled(),
+                rounding: RoundingProfile::disabled(),
+                estimation: EstimationProfile::disabled(),
+                number_theory: NumberTheoryProfile {
+                    weight: 1,
+                    ..NumberTheoryProfile::disabled()
+                },
+                equation: EquationProfile {
+                    weight: 1,
+                    ..EquationProfile::disabled()
+                },
+                sequence: SequenceProfile {
+                    weight: 1,
+                    geometric: true,
+                    decreasing: true,
+                    ..SequenceProfile::disabled()
+                },
+                comparison: ComparisonProfile::disabled(),
+                word_problem: WordProblemProfile::grade(5),
+                money: MoneyProfile::disabled(),
+                time: TimeProfile::disabled(),
+                unit: UnitProfile {
+                    weight: 1,
+                    length: true,
+                    mass: true,
+                    volume: true,
+                    decimals: true,
+                    imperial: false,
+                },
+            },
+            _ => Self {
+                addition: OperationProfile::new(1, 1000..=100000),
+                subtraction: OperationProfile::new(1, 1000..=100000),
+                multiplication: OperationProfile::new(2, 10..=999),
+                division: OperationProfile::new(2, 12..=200),
+                multi_step: MultiStepProfile {
+                    weight: 2,
+                    terms: 3..=4,
+                    operands: 2..=100,
+                    max_value: 10000,
+                },
+                fraction: FractionProfile {
+                    weight: 2,
+                    denominators: 2..=12,
+                    unlike_denominators: true,
+                    multiply_divide: true,
+                },
+                decimal: DecimalProfile {
+                    weight: 2,
+                    whole: 0..=1000,
+                    places: 3,
+                    multiply: true,
+                },
+                remainder: OperationProfile::disabled(),
+                missing_operand: 1,
+                negative_numbers: false,
+                carries: None,
+                borrows: None,
+                times_table: TimesTableProfile::disabled(),
+                power: PowerProfile {
+                    weight: 1,
+                    bases: 2..=20,
+                    max_exponent: 5,
+                    max_value: 1000,
+                    square_roots: true,
+                    cube_roots: true,
+                },
+                percent: PercentProfile {
+                    weight: 1,
+                    wholes: 10..=500,
+                    what_percent: true,
+                },
+                ratio: RatioProfile {
+                    weight: 1,
+                    terms: 1..=12,
+                    max_factor: 12,
+                },
+                rounding: RoundingProfile::disabled(),
+                estimation: EstimationProfile::disabled(),
+                number_theory: NumberTheoryProfile {
+                    weight: 1,
+                    ..NumberTheoryProfile::disabled()
+                },
+                equation: EquationProfile {
+                    weight: 1,
+                    brackets: true,
+                    ..EquationProfile::disabled()
+                },
+                sequence: SequenceProfile {
+                    weight: 1,
+                    geometric: true,
+                    decreasing: true,
+                    ..SequenceProfile::disabled()
+                },
+                comparison: ComparisonProfile::disabled(),
+                word_problem: WordProblemProfile::grade(6),
+                money: MoneyProfile::disabled(),
+                time: TimeProfile::disabled(),
+                unit: UnitProfile::disabled(),
+            },
+        }
+    }
+
+    /// Checks every part of the profile against the rules in its docs, e.g. empty ranges,
+    /// rounding to a place without a name or sequences with terms that overflow.
+    ///
+    /// The presets are valid, profiles made outside of them should be checked.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.addition.validate("addition")?;
+        self.subtraction.validate("subtraction")?;
+        self.multiplication.validate("multiplication")?;
+        self.division.validate("division")?;
+        self.multi_step.validate()?;
+        self.fraction.validate()?;
+        self.decimal.validate()?;
+        self.remainder.validate("remainder")?;
+        self.times_table.validate()?;
+        self.power.validate()?;
+        self.percent.validate()?;
+        self.ratio.validate()?;
+        self.rounding.validate()?;
+        self.estimation.validate()?;
+        self.number_theory.validate()?;
+        self.equation.validate()?;
+        self.sequence.validate()?;
+        self.comparison.validate()?;
+        self.money.validate()?;
+        self.time.validate()?;
+        Ok(())
+    }
+
+    /// Drill of the given times tables up to x12, including the division facts,
+    /// no other questions are asked.
+    pub fn times_tables(tables: Vec<i64>) -> Self {
+        Self {
+            addition: OperationProfile::disabled(),
+            subtraction: OperationProfile::disabled(),
+            multiplication: OperationProfile::disabled(),
+            division: OperationProfile::disabled(),
+            multi_step: MultiStepProfile::disabled(),
+            fraction: FractionProfile::disabled(),
+            decimal: DecimalProfile::disabled(),
+            remainder: OperationProfile::disabled(),
+            missing_operand: 0,
+            negative_numbers: false,
+            carries: None,
+            borrows: None,
+            times_table: TimesTableProfile {
+                weight: 1,
+                tables,
+                max_factor: 12,
+                divide: true,
+            },
+            power: PowerProfile::disabled(),
+            percent: PercentProfile::disabled(),
+            ratio: RatioProfile::disabled(),
+            rounding: RoundingProfile::disabled(),
+            estimation: EstimationProfile::disabled(),
+            number_theory: NumberTheoryProfile::disabled(),
+            equation: EquationProfile::disabled(),
+            sequence: SequenceProfile::disabled(),
+            comparison: ComparisonProfile::disabled(),
+            word_problem: WordProblemProfile::disabled(),
+            money: MoneyProfile::disabled(),
+            time: TimeProfile::disabled(),
+            unit: UnitProfile::disabled(),
+        }
+    }
+}
+
+impl Default for DifficultyProfile {
+    fn default() -> Self {
+        Self {
+            addition: OperationProfile::new(2, 2..=1000),
+            subtraction: OperationProfile::new(2, 2..=1000),
+            multiplication: OperationProfile::new(2, 2..=100),
+            // Reduce the chance of division
+            division: OperationProfile::new(1, 9..=50),
+            multi_step: MultiStepProfile::disabled(),
+            fraction: FractionProfile::disabled(),
+            decimal: DecimalProfile::disabled(),
+            remainder: OperationProfile::disabled(),
+            missing_operand: 0,
+            negative_numbers: false,
+            carries: None,
+            borrows: None,
+            times_table: TimesTableProfile::disabled(),
+            power: PowerProfile::disabled(),
+            percent: PercentProfile::disabled(),
+            ratio: RatioProfile::disabled(),
+            rounding: RoundingProfile::disabled(),
+            estimation: EstimationProfile::disabled(),
+            number_theory: NumberTheoryProfile::disabled(),
+            equation: EquationProfile::disabled(),
+            sequence: SequenceProfile::disabled(),
+            comparison: ComparisonProfile::disabled(),
+            word_problem: WordProblemProfile::disabled(),
+            money: MoneyProfile::disabled(),
+            time: TimeProfile::disabled(),
+            unit: UnitProfile::disabled(),
+        }
+    }
+}
+
+/// Fails if the range is empty or starts below `min`.
+fn check_range<T: PartialOrd + Debug>(
+    name: &str,
+    range: &RangeInclusive<T>,
+    min: Option<T>,
+) -> anyhow::Result<()> {
+    if range.is_empty() {
+        anyhow::bail!("Invalid {} {:?}, the range is empty", name, range);
+    }
+    match min {
+        Some(min) if *range.start() < min => anyhow::bail!(
+            "Invalid {} {:?}, they must start at {:?} or above",
+            name,
+            range,
+            min
+        ),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_are_valid() {
+        for grade in 1..=6 {
+            DifficultyProfile::grade(grade).validate().unwrap();
+        }
+        DifficultyProfile::default().validate().unwrap();
+        DifficultyProfile::times_tables(vec![6, 9])
+            .validate()
+            .unwrap();
+    }
+
+    #[test]
+    fn rejects_ranges_against_the_docs() {
+        let broken: [fn(&mut DifficultyProfile); 8] = [
+            |profile| profile.addition.operands = RangeInclusive::new(5, 1),
+            |profile| profile.times_table.tables = vec![],
+            |profile| profile.times_table.tables = vec![6, 0],
+            |profile| profile.ratio.terms = 0..=10,
+            |profile| profile.estimation.operands = 5..=999,
+            |profile| profile.equation.coefficients = 0..=9,
+            |profile| profile.time.step = 0,
+            |profile| profile.time.max_duration = 1,
+        ];
+        for (index, breaking) in broken.iter().enumerate() {
+            let mut profile = DifficultyProfile::grade(3);
+            breaking(&mut profile);
+            assert!(profile.validate().is_err(), "{}", index);
+        }
+    }
+
+    #[test]
+    fn rejects_denominators_without_proper_fractions() {
+        let mut profile = DifficultyProfile::grade(5);
+        for denominators in [1..=12, 0..=1, RangeInclusive::new(6, 3)] {
+            profile.fraction.denominators = denominators;
+            assert!(profile.validate().is_err());
+        }
+        profile.fraction.denominators = 2..=2;
+        profile.validate().unwrap();
+    }
+
+    #[test]
+    fn rejects_decimal_factors_above_9() {
+        let mut profile = DifficultyProfile::grade(6);
+        assert!(profile.decimal.multiply);
+        profile.decimal.whole = 10..=1000;
+        assert!(profile.validate().is_err());
+        profile.decimal.multiply = false;
+        profile.validate().unwrap();
+        profile.decimal.places = 0;
+        assert!(profile.validate().is_err());
+        profile.decimal.places = 18;
+        assert!(profile.validate().is_err());
+        profile.decimal.whole = RangeInclusive::new(5, 1);
+        profile.decimal.places = 2;
+        assert!(profile.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_powers_above_the_largest_value() {
+        let mut profile = DifficultyProfile::grade(6);
+        profile.validate().unwrap();
+        profile.power.bases = 4..=20;
+        assert!(profile.validate().is_err());
+        profile.power.max_exponent = 4;
+        profile.validate().unwrap();
+        profile.power.max_exponent = 2;
+        profile.power.max_value = 50;
+        assert!(profile.validate().is_err());
+        profile.power.cube_roots = false;
+        profile.validate().unwrap();
+        profile.power.bases = -2..=20;
+        assert!(profile.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_prices_without_multiples_of_the_step() {
+        let mut profile = DifficultyProfile::grade(3);
+        profile.money.step = 50;
+        profile.money.prices = 1..=40;
+        assert!(profile.validate().is_err());
+        profile.money.step = 100;
+        profile.money.prices = 150..=160;
+        assert!(profile.validate().is_err());
+        // Prices per item go up to a sixth of the largest price
+        profile.money.prices = 150..=250;
+        assert!(profile.validate().is_err());
+        profile.money.unit_prices = false;
+        profile.validate().unwrap();
+        profile.money.step = 0;
+        assert!(profile.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_unnamed_rounding_places() {
+        let mut profile = DifficultyProfile::grade(3);
+        for places in [0..=2, 1..=7, RangeInclusive::new(3, 2)] {
+            profile.rounding.places = places;
+            assert!(profile.validate().is_err());
+        }
+        profile.rounding.places = 1..=6;
+        profile.validate().unwrap();
+    }
+
+    #[test]
+    fn rejects_sequences_that_overflow() {
+        let mut profile = DifficultyProfile::grade(6);
+        assert!(profile.sequence.geometric);
+        profile.validate().unwrap();
+        profile.sequence.ratios = 2..=3;
+        profile.sequence.terms = 3..=40;
+        assert!(profile.validate().is_err());
+        profile.sequence.terms = 3..=39;
+        profile.validate().unwrap();
+
+        let mut profile = DifficultyProfile::grade(6);
+        profile.sequence.steps = 1..=i64::MAX / 4;
+        assert!(profile.validate().is_err());
+        profile.sequence.steps = 1..=i64::MAX / 8;
+        profile.validate().unwrap();
+    }
+
+    #[test]
+    fn rejects_empty_sequence_ranges() {
+        let profile = DifficultyProfile::grade(6);
+        let mut sequence = profile.sequence.clone();
+        sequence.terms = 2..=5;
+        assert!(sequence.validate().is_err());
+        let mut sequence = profile.sequence.clone();
+        sequence.steps = 0..=5;
+        assert!(sequence.validate().is_err());
+        let mut sequence = profile.sequence.clone();
+        sequence.ratios = 1..=3;
+        assert!(sequence.validate().is_err());
+        sequence.geometric = false;
+        sequence.validate().unwrap();
+        sequence.starts = RangeInclusive::new(5, 1);
+        assert!(sequence.validate().is_err());
+    }
+}
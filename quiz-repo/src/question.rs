@@ -1,40 +1,78 @@
 use std::fmt::Display;
 
 use log::trace;
-use rand::Rng;
+use rand::RngCore;
+
+use crate::{
+    answer::Answer,
+    expr::{Expr, Notation},
+    generator::GeneratorRegistry,
+    grading::Grading,
+    kind::QuestionKind,
+    profile::DifficultyProfile,
+};
 
 #[derive(Clone)]
 pub struct Question {
     id: i64,
+    kind: QuestionKind,
     question: String,
-    expected_answer: i64,
-    user_answer: Option<i64>,
+    expected_answer: Answer,
+    user_answer: Option<Answer>,
+    /// Name of the generator, `None` for questions stored before generators existed.
+    generator: Option<String>,
+    grading: Grading,
 }
 
 impl Question {
-    pub fn new() -> Self {
-        let mut rng = rand::thread_rng();
-        let (question, answer) = generate_question(&mut rng);
-        trace!("Generated question: {} = {}", question, answer);
-        Self {
+    pub fn new(profile: &DifficultyProfile) -> Self {
+        Self::generate(
+            &GeneratorRegistry::from_profile(profile),
+            &mut rand::thread_rng(),
+        )
+        .expect("The built-in generators are always registered")
+    }
+
+    /// Generates a question with one of the generators in the registry,
+    /// `None` if the registry is empty.
+    pub fn generate(registry: &GeneratorRegistry, rng: &mut dyn RngCore) -> Option<Self> {
+        let (generator, kind, answer) = registry.generate(rng)?;
+        let grading = generator.grading(&kind, &answer);
+        let question = kind.render(Notation::Plain);
+        trace!(
+            "Generated question: {}, answer: {}, generator: {}",
+            question,
+            answer,
+            generator.name()
+        );
+        Some(Self {
             id: 0,
+            kind,
             question,
             expected_answer: answer,
             user_answer: None,
-        }
+            generator: Some(generator.name().to_string()),
+            grading,
+        })
     }
 
     pub fn from_question(
         id: i64,
+        kind: QuestionKind,
         question: String,
-        expected_answer: i64,
-        user_answer: Option<i64>,
+        expected_answer: Answer,
+        user_answer: Option<Answer>,
+        generator: Option<String>,
+        grading: Grading,
     ) -> Self {
         Self {
             id,
+            kind,
             question,
             expected_answer,
             user_answer,
+            generator,
+            grading,
         }
     }
 
@@ -46,63 +84,87 @@ impl Question {
         self.question.clone()
     }
 
-    pub fn get_expected_answer(&self) -> i64 {
-        self.expected_answer
+    pub fn get_kind(&self) -> &QuestionKind {
+        &self.kind
     }
 
-    pub fn get_answer(&self) -> Option<i64> {
+    pub fn get_generator(&self) -> Option<&str> {
+        self.generator.as_deref()
+    }
+
+    pub fn get_grading(&self) -> &Grading {
+        &self.grading
+    }
+
+    /// The arithmetic expression, `None` for questions written out in words.
+    pub fn get_expression(&self) -> Option<&Expr> {
+        self.kind.expression()
+    }
+
+    /// Renders the question in another notation, e.g. for LaTeX output.
+    pub fn render(&self, notation: Notation) -> String {
+        self.kind.render(notation)
+    }
+
+    pub fn get_expected_answer(&self) -> Answer {
+        self.expected_answer.clone()
+    }
+
+    pub fn get_answer(&self) -> Option<Answer> {
+        self.user_answer.clone()
+    }
+
+    /// Names of the wrong parts of the user answer, e.g. `["remainder"]`,
+    /// empty if the question is unanswered or answered correctly.
+    pub fn wrong_parts(&self) -> Vec<&'static str> {
         self.user_answer
+            .as_ref()
+            .filter(|answer| !self.grading.is_correct(&self.expected_answer, answer))
+            .map(|answer| self.expected_answer.wrong_parts(answer))
+            .unwrap_or_default()
     }
 
     fn get_input(&self) -> String {
-        self.user_answer.map(|x| x.to_string()).unwrap_or_default()
+        self.user_answer
+            .as_ref()
+            .map(|x| x.to_string())
+            .unwrap_or_default()
     }
 }
 
 impl Default for Question {
     fn default() -> Self {
-        Self::new()
+        Self::new(&DifficultyProfile::default())
     }
 }
 
 impl Display for Question {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} = {}", self.question, self.get_input())
-    }
-}
-
-fn generate_question<R: Rng>(rng: &mut R) -> (String, i64) {
-    // Reduce the chance of division
-    let distribution = [0, 0, 1, 1, 2, 2, 3];
-    let op = distribution[rng.gen_range(0..distribution.len())];
-
-    match op {
-        0 => {
-            // Addition
-            let a = rng.gen_range(2..=1000);
-            let b = rng.gen_range(2..=1000);
-            (format!("{} + {}", a, b), a + b)
-        }
-        1 => {
-            // Subtraction, ensure result is non-negative
-            let a = rng.gen_range(2..=1000);
-            let b = rng.gen_range(2..=1000);
-            let (a, b) = if a >= b { (a, b) } else { (b, a) };
-            (format!("{} - {}", a, b), a - b)
-        }
-        2 => {
-            // Multiplication
-            let a = rng.gen_range(2..=100);
-            let b = rng.gen_range(2..=100);
-            (format!("{} x {}", a, b), a * b)
-        }
-        3 => {
-            // Division, ensure result is an integer
-            let divisor = rng.gen_range(9..=50);
-            let quotient = rng.gen_range(9..=50);
-            let dividend = divisor * quotient;
-            (format!("{} ÷ {}", dividend, divisor), quotient)
+        match self.kind {
+            // The input goes into the blank
+            QuestionKind::MissingOperand { .. }
+            | QuestionKind::Sequence { .. }
+            | QuestionKind::ConvertUnits { .. }
+                if self.user_answer.is_some() =>
+            {
+                write!(f, "{}", self.question.replacen('?', &self.get_input(), 1))
+            }
+            QuestionKind::MissingOperand { .. }
+            | QuestionKind::Sequence { .. }
+            | QuestionKind::ConvertUnits { .. } => write!(f, "{}", self.question),
+            // The relation goes into the box
+            QuestionKind::Compare { .. } | QuestionKind::CompareUnitPrices { .. }
+                if self.user_answer.is_some() =>
+            {
+                write!(f, "{}", self.question.replacen('☐', &self.get_input(), 1))
+            }
+            QuestionKind::Compare { .. } | QuestionKind::CompareUnitPrices { .. } => {
+                write!(f, "{}", self.question)
+            }
+            QuestionKind::Equation { .. } => {
+                write!(f, "{}, x = {}", self.question, self.get_input())
+            }
+            _ => write!(f, "{} = {}", self.question, self.get_input()),
         }
-        _ => unreachable!(),
     }
 }
@@ -0,0 +1,261 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// A fraction that is always kept in reduced form with a positive denominator,
+/// so the derived equality compares values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Rational {
+    numer: i64,
+    denom: i64,
+}
+
+/// The greatest common divisor, `None` if it doesn't fit into an `i64`,
+/// i.e. for `i64::MIN` with 0 or itself.
+pub(crate) fn gcd(a: i64, b: i64) -> Option<i64> {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    i64::try_from(a).ok()
+}
+
+impl Rational {
+    /// Creates a reduced fraction, `None` if the denominator is 0 or the reduced form
+    /// overflows, e.g. `i64::MIN / -1`.
+    pub fn new(numer: i64, denom: i64) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+        let divisor = gcd(numer, denom)?.checked_mul(denom.signum())?;
+        Some(Self {
+            numer: numer.checked_div(divisor)?,
+            denom: denom.checked_div(divisor)?,
+        })
+    }
+
+    pub fn numer(&self) -> i64 {
+        self.numer
+    }
+
+    pub fn denom(&self) -> i64 {
+        self.denom
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denom == 1
+    }
+
+    /// The value as an integer, `None` if it has a fractional part.
+    pub fn to_integer(&self) -> Option<i64> {
+        self.is_integer().then_some(self.numer)
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let denom = self.denom.checked_mul(other.denom)?;
+        let numer = self
+            .numer
+            .checked_mul(other.denom)?
+            .checked_add(other.numer.checked_mul(self.denom)?)?;
+        Self::new(numer, denom)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_add(Self {
+            numer: other.numer.checked_neg()?,
+            denom: other.denom,
+        })
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        Self::new(
+            self.numer.checked_mul(other.numer)?,
+            self.denom.checked_mul(other.denom)?,
+        )
+    }
+
+    /// `None` on overflow or division by zero.
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        Self::new(
+            self.numer.checked_mul(other.denom)?,
+            self.denom.checked_mul(other.numer)?,
+        )
+    }
+
+    pub fn checked_pow(self, exponent: u32) -> Option<Self> {
+        Some(Self {
+            numer: self.numer.checked_pow(exponent)?,
+            denom: self.denom.checked_pow(exponent)?,
+        })
+    }
+
+    /// The exact `index`-th root, `None` if it's not rational,
+    /// e.g. the square root of `9/4` is `3/2` but there is none for `2`.
+    pub fn checked_root(self, index: u32) -> Option<Self> {
+        if index == 0 || (self.numer < 0 && index % 2 == 0) {
+            return None;
+        }
+        let root = |n: i64| -> Option<i64> {
+            let abs = n.checked_abs()?;
+            let guess = (abs as f64).powf(1.0 / index as f64).round() as i64;
+            (guess.saturating_sub(1).max(0)..=guess.saturating_add(1))
+                .find(|r| r.checked_pow(index) == Some(abs))
+                .map(|r| r * n.signum())
+        };
+        Some(Self {
+            numer: root(self.numer)?,
+            denom: root(self.denom)?,
+        })
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(n: i64) -> Self {
+        Self { numer: n, denom: 1 }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.numer as i128 * other.denom as i128).cmp(&(other.numer as i128 * self.denom as i128))
+    }
+}
+
+/// `3/4`, or just `3` for whole numbers.
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+/// Parses `a/b` or a whole number, the result is reduced.
+impl FromStr for Rational {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || anyhow::Error::msg(format!("Invalid fraction: {}", s));
+        match s.split_once('/') {
+            Some((numer, denom)) => Self::new(
+                numer.trim().parse().map_err(|_| invalid())?,
+                denom.trim().parse().map_err(|_| invalid())?,
+            )
+            .ok_or_else(invalid),
+            None => Ok(Self::from(s.trim().parse::<i64>().map_err(|_| invalid())?)),
+        }
+    }
+}
+
+impl From<Rational> for String {
+    fn from(r: Rational) -> Self {
+        r.to_string()
+    }
+}
+
+impl TryFrom<String> for Rational {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn r(numer: i64, denom: i64) -> Rational {
+        Rational::new(numer, denom).unwrap()
+    }
+
+    #[test]
+    fn keeps_reduced_form() {
+        assert_eq!(r(2, 4), r(1, 2));
+        assert_eq!(r(6, -8), r(-3, 4));
+        assert_eq!(r(-6, -8).to_string(), "3/4");
+        assert_eq!(r(0, -5), Rational::from(0));
+        assert_eq!(r(12, 4).to_integer(), Some(3));
+        assert_eq!(Rational::new(1, 0), None);
+    }
+
+    #[test]
+    fn parses_fractions() {
+        assert_eq!("3/4".parse::<Rational>().unwrap(), r(3, 4));
+        assert_eq!(" 10 / -4 ".parse::<Rational>().unwrap(), r(-5, 2));
+        assert_eq!("7".parse::<Rational>().unwrap(), Rational::from(7));
+        for text in ["", "1/0", "1/", "/2", "a/b", "1.5", "1/2/3"] {
+            assert!(text.parse::<Rational>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn min_numerator() {
+        assert_eq!(r(i64::MIN, 1).numer(), i64::MIN);
+        assert_eq!(r(i64::MIN, 2), Rational::from(i64::MIN / 2));
+        assert_eq!(r(i64::MIN, 6), r(i64::MIN / 2, 3));
+        assert_eq!(Rational::new(i64::MIN, -1), None);
+        assert_eq!(Rational::new(i64::MIN, 0), None);
+        assert!("-9223372036854775808/-1".parse::<Rational>().is_err());
+        assert!("-9223372036854775808/1".parse::<Rational>().is_ok());
+    }
+
+    #[test]
+    fn min_denominator() {
+        assert_eq!(r(2, i64::MIN), r(-1, i64::MIN / -2));
+        assert_eq!(Rational::new(1, i64::MIN), None);
+        assert_eq!(Rational::new(-1, i64::MIN), None);
+        assert!("1/-9223372036854775808".parse::<Rational>().is_err());
+    }
+
+    #[test]
+    fn arithmetic_is_none_on_overflow() {
+        let min = Rational::from(i64::MIN);
+        let max = Rational::from(i64::MAX);
+        assert_eq!(min.checked_div(Rational::from(-1)), None);
+        assert_eq!(min.checked_mul(Rational::from(-1)), None);
+        assert_eq!(Rational::from(0).checked_sub(min), None);
+        assert_eq!(max.checked_add(Rational::from(1)), None);
+        assert_eq!(r(1, 3).checked_div(Rational::from(0)), None);
+        assert_eq!(Rational::from(2).checked_pow(63), None);
+        assert_eq!(min.checked_root(3), None);
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(r(1, 2).checked_add(r(1, 3)), Some(r(5, 6)));
+        assert_eq!(r(1, 2).checked_sub(r(1, 2)), Some(Rational::from(0)));
+        assert_eq!(r(2, 3).checked_mul(r(3, 4)), Some(r(1, 2)));
+        assert_eq!(r(2, 3).checked_div(r(-4, 9)), Some(r(-3, 2)));
+        assert_eq!(r(-2, 3).checked_pow(3), Some(r(-8, 27)));
+    }
+
+    #[test]
+    fn roots() {
+        assert_eq!(r(9, 4).checked_root(2), Some(r(3, 2)));
+        assert_eq!(
+            Rational::from(-27).checked_root(3),
+            Some(Rational::from(-3))
+        );
+        assert_eq!(Rational::from(-4).checked_root(2), None);
+        assert_eq!(Rational::from(2).checked_root(2), None);
+        assert_eq!(Rational::from(i64::MAX).checked_root(2), None);
+        assert_eq!(Rational::from(4).checked_root(0), None);
+    }
+
+    #[test]
+    fn compares_values() {
+        assert!(r(1, 3) < r(1, 2));
+        assert!(r(-1, 2) < r(-1, 3));
+        assert!(Rational::from(i64::MIN) < r(i64::MAX, 2));
+    }
+}
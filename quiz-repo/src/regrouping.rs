@@ -0,0 +1,141 @@
+use std::{fmt::Display, str::FromStr};
+
+/// Carrying in column addition, or borrowing in column subtraction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Regrouping {
+    Carry,
+    Borrow,
+}
+
+impl Regrouping {
+    /// Stored in the `regrouping` column.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Regrouping::Carry => "carry",
+            Regrouping::Borrow => "borrow",
+        }
+    }
+}
+
+/// How many carries or borrows a question may have.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegroupingCount {
+    None,
+    One,
+    Multiple,
+}
+
+impl RegroupingCount {
+    pub fn matches(&self, count: u32) -> bool {
+        match self {
+            RegroupingCount::None => count == 0,
+            RegroupingCount::One => count == 1,
+            RegroupingCount::Multiple => count > 1,
+        }
+    }
+}
+
+impl Display for RegroupingCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegroupingCount::None => write!(f, "none"),
+            RegroupingCount::One => write!(f, "one"),
+            RegroupingCount::Multiple => write!(f, "multiple"),
+        }
+    }
+}
+
+/// Parses `none`, `one` or `multiple`.
+impl FromStr for RegroupingCount {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "none" => Ok(RegroupingCount::None),
+            "one" => Ok(RegroupingCount::One),
+            "multiple" => Ok(RegroupingCount::Multiple),
+            _ => anyhow::bail!(
+                "Invalid regrouping count: {}, expected none, one or multiple",
+                s
+            ),
+        }
+    }
+}
+
+/// Number of carries when adding `a` and `b` column by column.
+pub(crate) fn count_carries(mut a: u64, mut b: u64) -> u32 {
+    let (mut carry, mut count) = (0, 0);
+    while a > 0 || b > 0 {
+        carry = (a % 10 + b % 10 + carry) / 10;
+        count += carry as u32;
+        a /= 10;
+        b /= 10;
+    }
+    count
+}
+
+/// Number of borrows when subtracting `b` from `a` column by column, `a` must not be
+/// smaller than `b`.
+pub(crate) fn count_borrows(mut a: u64, mut b: u64) -> u32 {
+    let (mut borrow, mut count) = (0, 0);
+    while a > 0 {
+        borrow = u64::from(a % 10 < b % 10 + borrow);
+        count += borrow as u32;
+        a /= 10;
+        b /= 10;
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carries() {
+        for (a, b, carries) in [
+            (0, 0, 0),
+            (23, 45, 0),
+            (38, 47, 1),
+            (999, 1, 3),
+            (95, 7, 2),
+            (1234, 5, 0),
+            (5, 1234, 0),
+            (1, 9999, 4),
+        ] {
+            assert_eq!(count_carries(a, b), carries, "{} + {}", a, b);
+        }
+    }
+
+    #[test]
+    fn borrows() {
+        for (a, b, borrows) in [
+            (0, 0, 0),
+            (45, 45, 0),
+            (58, 27, 0),
+            (52, 27, 1),
+            (1000, 1, 3),
+            (100, 99, 2),
+            (503, 8, 2),
+            (1234, 4, 0),
+        ] {
+            assert_eq!(count_borrows(a, b), borrows, "{} - {}", a, b);
+        }
+    }
+
+    #[test]
+    fn regrouping_counts() {
+        for count in [
+            RegroupingCount::None,
+            RegroupingCount::One,
+            RegroupingCount::Multiple,
+        ] {
+            assert_eq!(count.to_string().parse::<RegroupingCount>().unwrap(), count);
+        }
+        assert!("two".parse::<RegroupingCount>().is_err());
+        assert!(RegroupingCount::None.matches(0));
+        assert!(RegroupingCount::One.matches(1));
+        assert!(!RegroupingCount::One.matches(2));
+        assert!(RegroupingCount::Multiple.matches(3));
+    }
+}
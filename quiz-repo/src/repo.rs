@@ -4,9 +4,19 @@ use chrono::{DateTime, TimeZone, Utc};
 use chrono_tz::Tz;
 use log::debug;
 use now::DateTimeNow;
+use rusqlite::{types::Type, Row};
 use tokio_rusqlite::Connection;
 
-use crate::question::Question;
+use crate::{
+    answer::Answer,
+    choices::Choices,
+    generator::{choices_rng, question_rng, GeneratorRegistry},
+    grading::Grading,
+    kind::QuestionKind,
+    migration,
+    profile::DifficultyProfile,
+    question::Question,
+};
 
 #[derive(Clone)]
 pub struct QuizRepo {
@@ -24,85 +34,171 @@ impl QuizRepo {
         };
         connection
             .call(|conn| {
-                conn.execute_batch(
-                    "BEGIN;
-                        CREATE TABLE IF NOT EXISTS questions (
-                        id INTEGER PRIMARY KEY,
-                        question TEXT NOT NULL,
-                        expected_answer INTEGER NOT NULL,
-                        answer INTEGER,
-                        created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-                        answered_at TIMESTAMP
-                    );
-                    CREATE INDEX IF NOT EXISTS idx_created_at ON questions (created_at);
-                    COMMIT;
-                ",
-                )?;
-                Ok(())
+                migration::migrate(conn).map_err(|e| tokio_rusqlite::Error::Other(e.into()))
             })
             .await?;
 
         Ok(Self { connection })
     }
 
-    pub async fn new_question(&self) -> anyhow::Result<Question> {
+    pub async fn new_question(&self, profile: &DifficultyProfile) -> anyhow::Result<Question> {
+        self.new_question_from(&GeneratorRegistry::from_profile(profile))
+            .await
+    }
+
+    /// Like `new_question`, with questions drawn from the generators of the registry.
+    /// Unanswered questions outside of sessions are asked again if they were drawn from
+    /// generators with the same parameters, e.g. the same times tables.
+    pub async fn new_question_from(
+        &self,
+        registry: &GeneratorRegistry,
+    ) -> anyhow::Result<Question> {
+        let registry = registry.clone();
         Ok(self
             .connection
-            .call(|conn| {
+            .call(move |conn| {
                 debug!("Finding existing unanswered question");
-                let mut stmt = conn.prepare("SELECT id, question, expected_answer FROM questions WHERE answer is NULL ORDER BY RANDOM() LIMIT 1")?;
-                if let Ok(question) = stmt.query_row([], |row| {
-                    Ok(Question::from_question(
-                        row.get(0)?,
-                        row.get(1)?,
-                        row.get(2)?,
-                        None,
-                    ))
-                }) {
+                let profile_id = profile_id(conn, &registry)?;
+                let mut stmt = conn.prepare("SELECT id, kind, expression, question, expected_answer, answer, generator, grading FROM questions WHERE answer is NULL AND session_id IS NULL AND profile_id = ?1 ORDER BY RANDOM() LIMIT 1")?;
+                if let Ok(question) = stmt.query_row([profile_id], question_from_row) {
                     debug!("Found existing unanswered question, id: {}, question: {}", question.get_id(), question.get_question());
                     return Ok(question);
                 }
 
                 debug!("Creating new question");
-                let mut stmt = conn.prepare("INSERT INTO questions (question, expected_answer) VALUES (?1, ?2) RETURNING id")?;
+                let question = Question::generate(&registry, &mut rand::thread_rng())
+                    .ok_or_else(no_generator)?;
+                Ok(insert_question(conn, &question, profile_id, None)?)
+            })
+            .await?)
+    }
 
-                let question = Question::new();
-                let id: i64 = stmt.query_row(
-                    (question.get_question(), question.get_expected_answer()),
+    /// Starts a session with reproducible questions, a random seed is picked if none is
+    /// given. Returns the id and the seed of the session.
+    pub async fn new_session(&self, seed: Option<u32>) -> anyhow::Result<(i64, u32)> {
+        let seed = seed.unwrap_or_else(rand::random);
+        Ok(self
+            .connection
+            .call(move |conn| {
+                let id: i64 = conn.query_row(
+                    "INSERT INTO sessions (seed) VALUES (?1) RETURNING id",
+                    [seed],
                     |row| row.get(0),
                 )?;
-                debug!("Insert new question, id: {}, question: {}", id, question.get_question());
-                let mut stmt = conn.prepare("SELECT id, question, expected_answer FROM questions WHERE id = ?1")?;
-                let question = stmt.query_row([id], |row| {
-                    Ok(Question::from_question(
-                        row.get(0)?,
-                        row.get(1)?,
-                        row.get(2)?,
-                        None,
-                    ))
-                })?;
-                debug!("Created new question, id: {}, question: {}", question.get_id(), question.get_question());
-                Ok(question)
+                debug!("Created session, id: {}, seed: {}", id, seed);
+                Ok((id, seed))
             })
             .await?)
     }
 
-    pub async fn answer_question(&self, id: i64, answer: i64) -> anyhow::Result<bool> {
+    /// The next question of a session. Sessions with the same seed and registry get the
+    /// same sequence of questions, an unanswered question is asked again before moving on.
+    pub async fn new_session_question(
+        &self,
+        session_id: i64,
+        registry: &GeneratorRegistry,
+    ) -> anyhow::Result<Question> {
+        let registry = registry.clone();
         Ok(self
             .connection
             .call(move |conn| {
-                debug!("Answering question, id: {}, answer: {}", id, answer);
-                conn.execute(
-                    "UPDATE questions SET answer = ?1, answered_at = CURRENT_TIMESTAMP WHERE id = ?2",
-                    (answer, id),
+                let mut stmt = conn.prepare("SELECT id, kind, expression, question, expected_answer, answer, generator, grading FROM questions WHERE answer is NULL AND session_id = ?1 ORDER BY sequence LIMIT 1")?;
+                if let Ok(question) = stmt.query_row([session_id], question_from_row) {
+                    debug!("Found existing unanswered question, id: {}, question: {}", question.get_id(), question.get_question());
+                    return Ok(question);
+                }
+
+                let seed: u32 = conn.query_row(
+                    "SELECT seed FROM sessions WHERE id = ?1",
+                    [session_id],
+                    |row| row.get(0),
                 )?;
-                let expected_answer: i64 = conn.query_row(
-                    "SELECT expected_answer FROM questions WHERE id = ?1",
-                    [id],
+                let sequence: i64 = conn.query_row(
+                    "SELECT COUNT(*) FROM questions WHERE session_id = ?1",
+                    [session_id],
                     |row| row.get(0),
                 )?;
-                debug!("The answer is correct: {}", expected_answer == answer);
-                Ok(expected_answer == answer)
+                debug!("Creating question {} of session {}, seed: {}", sequence, session_id, seed);
+                let question = Question::generate(&registry, &mut question_rng(seed, sequence as u64))
+                    .ok_or_else(no_generator)?;
+                let profile_id = profile_id(conn, &registry)?;
+                Ok(insert_question(conn, &question, profile_id, Some((session_id, sequence)))?)
+            })
+            .await?)
+    }
+
+    /// Records the user input as the answer, the input is parsed according to the type
+    /// of the expected answer, e.g. `3/4` for fraction questions, and graded by the
+    /// grading policy of the question.
+    pub async fn answer_question(&self, id: i64, answer: &str) -> anyhow::Result<bool> {
+        let input = answer.to_string();
+        Ok(self
+            .connection
+            .call(move |conn| {
+                debug!("Answering question, id: {}, answer: {}", id, input);
+                let (expected_answer, grading, choices): (Answer, Grading, Option<Choices>) =
+                    conn.query_row(
+                        "SELECT expected_answer, grading, choices FROM questions WHERE id = ?1",
+                        [id],
+                        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                    )?;
+                let answer = expected_answer
+                    .parse_input(&input)
+                    .map_err(|e| tokio_rusqlite::Error::Other(e.into()))?;
+                let correct = grading.is_correct(&expected_answer, &answer);
+                // Which typical error the picked choice comes from
+                let misconception = choices
+                    .filter(|_| !correct)
+                    .and_then(|choices| choices.misconception(&answer));
+                conn.execute(
+                    "UPDATE questions SET answer = ?1, correct = ?2, misconception = ?3, answered_at = CURRENT_TIMESTAMP WHERE id = ?4",
+                    (&answer, correct, misconception.map(|m| m.name()), id),
+                )?;
+                debug!("The answer is correct: {}", correct);
+                Ok(correct)
+            })
+            .await?)
+    }
+
+    /// Offers the question as multiple choice. The choices are made on the first call
+    /// and kept, so a question asked again offers the same ones. Sessions with the same
+    /// seed get the same choices too.
+    pub async fn multiple_choice(&self, question: &Question) -> anyhow::Result<Choices> {
+        let (id, kind, expected_answer, grading) = (
+            question.get_id(),
+            question.get_kind().clone(),
+            question.get_expected_answer(),
+            question.get_grading().clone(),
+        );
+        Ok(self
+            .connection
+            .call(move |conn| {
+                let (choices, seed, sequence): (Option<Choices>, Option<u32>, Option<i64>) = conn
+                    .query_row(
+                        "SELECT questions.choices, sessions.seed, questions.sequence FROM questions LEFT JOIN sessions ON sessions.id = questions.session_id WHERE questions.id = ?1",
+                        [id],
+                        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                    )?;
+                if let Some(choices) = choices {
+                    return Ok(choices);
+                }
+                let choices = match seed.zip(sequence) {
+                    Some((seed, sequence)) => Choices::generate(
+                        &kind,
+                        &expected_answer,
+                        &grading,
+                        &mut choices_rng(seed, sequence as u64),
+                    ),
+                    None => {
+                        Choices::generate(&kind, &expected_answer, &grading, &mut rand::thread_rng())
+                    }
+                };
+                conn.execute(
+                    "UPDATE questions SET choices = ?1 WHERE id = ?2",
+                    (&choices, id),
+                )?;
+                debug!("Offering choices for question {}: {:?}", id, choices);
+                Ok(choices)
             })
             .await?)
     }
@@ -117,7 +213,7 @@ impl QuizRepo {
             .connection
             .call(move |conn| {
                 let correct: i64 = conn.query_row(
-                    "SELECT COUNT(*) FROM questions WHERE answer is not NULL AND answer = expected_answer AND answered_at BETWEEN ?1 AND ?2",
+                    "SELECT COUNT(*) FROM questions WHERE answer is not NULL AND correct AND answered_at BETWEEN ?1 AND ?2",
                     [
                         start.unwrap_or_else(|| Utc::now() - chrono::Duration::days(1000)),
                         end.unwrap_or_else(Utc::now),
@@ -137,15 +233,77 @@ impl QuizRepo {
                     }).await?)
     }
 
-    pub async fn mistake_collection(&self) -> anyhow::Result<Vec<(i64, String, Option<i64>)>> {
+    /// Wrongly answered questions, together with the given answers.
+    pub async fn mistake_collection(&self) -> anyhow::Result<Vec<Question>> {
         Ok(self
             .connection
             .call(|conn| {
                 let mut stmt = conn.prepare(
-                    "SELECT id, question, answer FROM questions WHERE answer is not null AND answer != expected_answer",
+                    "SELECT id, kind, expression, question, expected_answer, answer, generator, grading FROM questions WHERE answer is not null AND NOT correct AND expression is not null",
                 )?;
                 let mut rows = stmt.query([])?;
                 let mut result = Vec::new();
+                while let Some(row) = rows.next()? {
+                    result.push(question_from_row(row)?);
+                }
+                Ok(result)
+            })
+            .await?)
+    }
+
+    /// Answered additions and subtractions grouped by the kind and number of regroupings,
+    /// as `(regrouping, regroupings, correct, total)`, e.g. `("borrow", 2, 7, 10)`.
+    pub async fn get_regrouping_statistics(&self) -> anyhow::Result<Vec<(String, i64, i64, i64)>> {
+        const SQL: &str = "
+        SELECT
+            regrouping,
+            regroupings,
+            SUM(CASE WHEN correct THEN 1 ELSE 0 END) AS correct_count,
+            COUNT(*) AS total_count
+        FROM
+            questions
+        WHERE
+            answer IS NOT NULL AND regrouping IS NOT NULL
+        GROUP BY
+            regrouping, regroupings
+        ORDER BY
+            regrouping, regroupings;";
+        Ok(self
+            .connection
+            .call(|conn| {
+                let mut stmt = conn.prepare(SQL)?;
+                let mut rows = stmt.query([])?;
+                let mut result = Vec::new();
+                while let Some(row) = rows.next()? {
+                    result.push((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?));
+                }
+                Ok(result)
+            })
+            .await?)
+    }
+
+    /// Answered unit conversions grouped by what the units measure,
+    /// as `(unit_family, correct, total)`, e.g. `("mass", 7, 10)`.
+    pub async fn get_unit_statistics(&self) -> anyhow::Result<Vec<(String, i64, i64)>> {
+        const SQL: &str = "
+        SELECT
+            unit_family,
+            SUM(CASE WHEN correct THEN 1 ELSE 0 END) AS correct_count,
+            COUNT(*) AS total_count
+        FROM
+            questions
+        WHERE
+            answer IS NOT NULL AND unit_family IS NOT NULL
+        GROUP BY
+            unit_family
+        ORDER BY
+            unit_family;";
+        Ok(self
+            .connection
+            .call(|conn| {
+                let mut stmt = conn.prepare(SQL)?;
+                let mut rows = stmt.query([])?;
+                let mut result = Vec::new();
                 while let Some(row) = rows.next()? {
                     result.push((row.get(0)?, row.get(1)?, row.get(2)?));
                 }
@@ -154,6 +312,35 @@ impl QuizRepo {
             .await?)
     }
 
+    /// How often each typical error was picked in multiple choice mode,
+    /// as `(misconception, count)`, the most frequent first.
+    pub async fn get_misconception_statistics(&self) -> anyhow::Result<Vec<(String, i64)>> {
+        const SQL: &str = "
+        SELECT
+            misconception,
+            COUNT(*) AS count
+        FROM
+            questions
+        WHERE
+            misconception IS NOT NULL
+        GROUP BY
+            misconception
+        ORDER BY
+            count DESC, misconception;";
+        Ok(self
+            .connection
+            .call(|conn| {
+                let mut stmt = conn.prepare(SQL)?;
+                let mut rows = stmt.query([])?;
+                let mut result = Vec::new();
+                while let Some(row) = rows.next()? {
+                    result.push((row.get(0)?, row.get(1)?));
+                }
+                Ok(result)
+            })
+            .await?)
+    }
+
     pub async fn get_daily_statistics(
         &self,
         year: i32,
@@ -181,8 +368,8 @@ impl QuizRepo {
         SELECT 
             DATE(DATETIME(answered_at, 'localtime')) AS local_date,
             COUNT(*) AS total_count,
-            SUM(CASE WHEN expected_answer = answer THEN 1 ELSE 0 END) AS correct_count,
-            ROUND(CAST(SUM(CASE WHEN expected_answer = answer THEN 1 ELSE 0 END) AS FLOAT) / COUNT(*) * 100, 2) AS correct_rate
+            SUM(CASE WHEN correct THEN 1 ELSE 0 END) AS correct_count,
+            ROUND(CAST(SUM(CASE WHEN correct THEN 1 ELSE 0 END) AS FLOAT) / COUNT(*) * 100, 2) AS correct_rate
         FROM 
             questions
         WHERE
@@ -205,3 +392,203 @@ impl QuizRepo {
             .await?)
     }
 }
+
+fn no_generator() -> tokio_rusqlite::Error {
+    tokio_rusqlite::Error::Other("No question generator registered".into())
+}
+
+/// The id of the parameters of every generator in the registry, stored the first time
+/// they are seen. Composite generators like `missing_operand` depend on the parameters
+/// of other generators, so all of them are kept together.
+fn profile_id(
+    conn: &rusqlite::Connection,
+    registry: &GeneratorRegistry,
+) -> tokio_rusqlite::Result<i64> {
+    let parameters = serde_json::to_string(
+        &registry
+            .generators()
+            .map(|generator| (generator.name(), generator.parameters()))
+            .collect::<Vec<_>>(),
+    )
+    .map_err(|e| tokio_rusqlite::Error::Other(e.into()))?;
+    conn.execute(
+        "INSERT OR IGNORE INTO profiles (parameters) VALUES (?1)",
+        [&parameters],
+    )?;
+    Ok(conn.query_row(
+        "SELECT id FROM profiles WHERE parameters = ?1",
+        [&parameters],
+        |row| row.get(0),
+    )?)
+}
+
+/// Stores a generated question, `session` is the session id and the position in it.
+fn insert_question(
+    conn: &rusqlite::Connection,
+    question: &Question,
+    profile_id: i64,
+    session: Option<(i64, i64)>,
+) -> rusqlite::Result<Question> {
+    let (kind, expression) = question
+        .get_kind()
+        .to_columns()
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    let regroupings = question.get_kind().regroupings();
+    let id: i64 = conn.query_row(
+        "INSERT INTO questions (question, kind, expression, expected_answer, grading, regrouping, regroupings, generator, session_id, sequence, canonical, unit_family, profile_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13) RETURNING id",
+        (
+            question.get_question(),
+            kind,
+            expression,
+            question.get_expected_answer(),
+            question.get_grading(),
+            regroupings.map(|(regrouping, _)| regrouping.name()),
+            regroupings.map(|(_, count)| count),
+            question.get_generator(),
+            session.map(|(session_id, _)| session_id),
+            session.map(|(_, sequence)| sequence),
+            question.get_kind().canonical(),
+            question.get_kind().unit_family().map(|family| family.name()),
+            profile_id,
+        ),
+        |row| row.get(0),
+    )?;
+    debug!(
+        "Insert new question, id: {}, question: {}",
+        id,
+        question.get_question()
+    );
+    let question = conn.query_row(
+        "SELECT id, kind, expression, question, expected_answer, answer, generator, grading FROM questions WHERE id = ?1",
+        [id],
+        question_from_row,
+    )?;
+    debug!(
+        "Created new question, id: {}, question: {}",
+        question.get_id(),
+        question.get_question()
+    );
+    Ok(question)
+}
+
+/// Maps a row of `SELECT id, kind, expression, question, expected_answer, answer, generator, grading`
+/// to a question.
+fn question_from_row(row: &Row) -> rusqlite::Result<Question> {
+    let kind: Option<String> = row.get(1)?;
+    let expression: String = row.get(2)?;
+    let kind = QuestionKind::from_columns(kind.as_deref(), &expression)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, Type::Text, Box::new(e)))?;
+    Ok(Question::from_question(
+        row.get(0)?,
+        kind,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+        row.get(7)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::Expr;
+
+    #[tokio::test]
+    async fn asks_unanswered_questions_again() {
+        let repo = QuizRepo::new(None::<&str>).await.unwrap();
+        let profile = DifficultyProfile::grade(3);
+        let question = repo.new_question(&profile).await.unwrap();
+        let again = repo.new_question(&profile).await.unwrap();
+        assert_eq!(again.get_id(), question.get_id());
+
+        let answer = question.get_expected_answer().to_string();
+        assert!(repo
+            .answer_question(question.get_id(), &answer)
+            .await
+            .unwrap());
+        let next = repo.new_question(&profile).await.unwrap();
+        assert_ne!(next.get_id(), question.get_id());
+    }
+
+    #[tokio::test]
+    async fn keeps_unanswered_questions_to_their_times_tables() {
+        let repo = QuizRepo::new(None::<&str>).await.unwrap();
+        let six = repo
+            .new_question(&DifficultyProfile::times_tables(vec![6]))
+            .await
+            .unwrap();
+        for _ in 0..10 {
+            let nine = repo
+                .new_question(&DifficultyProfile::times_tables(vec![9]))
+                .await
+                .unwrap();
+            assert_ne!(nine.get_id(), six.get_id());
+            let expression = nine.get_expression().unwrap();
+            assert!(expression.operands().contains(&&Expr::num(9)), "{}", nine);
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_unanswered_questions_to_their_profile() {
+        let repo = QuizRepo::new(None::<&str>).await.unwrap();
+        let positive = DifficultyProfile::grade(2);
+        let negative = DifficultyProfile {
+            negative_numbers: true,
+            ..DifficultyProfile::grade(2)
+        };
+        let question = repo.new_question(&positive).await.unwrap();
+        let other = repo.new_question(&negative).await.unwrap();
+        assert_ne!(other.get_id(), question.get_id());
+        let again = repo.new_question(&positive).await.unwrap();
+        assert_eq!(again.get_id(), question.get_id());
+    }
+
+    /// The questions of a new session with the seed, each answered to move on.
+    async fn session_questions(repo: &QuizRepo, seed: u32, count: usize) -> Vec<String> {
+        let registry = GeneratorRegistry::from_profile(&DifficultyProfile::grade(4));
+        let (session, _) = repo.new_session(Some(seed)).await.unwrap();
+        let mut questions = vec![];
+        for _ in 0..count {
+            let question = repo.new_session_question(session, &registry).await.unwrap();
+            let answer = question.get_expected_answer().to_string();
+            repo.answer_question(question.get_id(), &answer)
+                .await
+                .unwrap();
+            questions.push(question.get_question());
+        }
+        questions
+    }
+
+    #[tokio::test]
+    async fn sessions_with_the_same_seed_ask_the_same_questions() {
+        let repo = QuizRepo::new(None::<&str>).await.unwrap();
+        let questions = session_questions(&repo, 7, 20).await;
+        assert_eq!(session_questions(&repo, 7, 20).await, questions);
+        assert_ne!(session_questions(&repo, 8, 20).await, questions);
+    }
+
+    #[tokio::test]
+    async fn offers_the_same_choices_in_sessions_with_the_same_seed() {
+        let repo = QuizRepo::new(None::<&str>).await.unwrap();
+        let registry = GeneratorRegistry::from_profile(&DifficultyProfile::grade(3));
+        let mut offered = vec![];
+        for _ in 0..2 {
+            let (session, _) = repo.new_session(Some(42)).await.unwrap();
+            let mut session_choices = vec![];
+            for _ in 0..5 {
+                let question = repo.new_session_question(session, &registry).await.unwrap();
+                let choices = repo.multiple_choice(&question).await.unwrap();
+                assert_eq!(repo.multiple_choice(&question).await.unwrap(), choices);
+                let answer = question.get_expected_answer().to_string();
+                repo.answer_question(question.get_id(), &answer)
+                    .await
+                    .unwrap();
+                session_choices.push(choices);
+            }
+            offered.push(session_choices);
+        }
+        assert!(offered[0].iter().any(|choices| choices.0.len() == 4));
+        assert_eq!(offered[0], offered[1]);
+    }
+}
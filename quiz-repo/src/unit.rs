@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+/// What a unit measures, stored in the `unit_family` column of conversion questions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitFamily {
+    Length,
+    Mass,
+    Volume,
+}
+
+impl UnitFamily {
+    pub fn name(&self) -> &'static str {
+        match self {
+            UnitFamily::Length => "length",
+            UnitFamily::Mass => "mass",
+            UnitFamily::Volume => "volume",
+        }
+    }
+}
+
+/// A unit of measurement, conversions stay within one family and one system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Unit {
+    Millimeter,
+    Centimeter,
+    Decimeter,
+    Meter,
+    Kilometer,
+    Gram,
+    Kilogram,
+    Tonne,
+    Milliliter,
+    Liter,
+    Inch,
+    Foot,
+    Yard,
+    Mile,
+    Ounce,
+    Pound,
+    Pint,
+    Quart,
+    Gallon,
+}
+
+impl Unit {
+    pub const ALL: &'static [Unit] = &[
+        Unit::Millimeter,
+        Unit::Centimeter,
+        Unit::Decimeter,
+        Unit::Meter,
+        Unit::Kilometer,
+        Unit::Gram,
+        Unit::Kilogram,
+        Unit::Tonne,
+        Unit::Milliliter,
+        Unit::Liter,
+        Unit::Inch,
+        Unit::Foot,
+        Unit::Yard,
+        Unit::Mile,
+        Unit::Ounce,
+        Unit::Pound,
+        Unit::Pint,
+        Unit::Quart,
+        Unit::Gallon,
+    ];
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Millimeter => "mm",
+            Unit::Centimeter => "cm",
+            Unit::Decimeter => "dm",
+            Unit::Meter => "m",
+            Unit::Kilometer => "km",
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Tonne => "t",
+            Unit::Milliliter => "mL",
+            Unit::Liter => "L",
+            Unit::Inch => "in",
+            Unit::Foot => "ft",
+            Unit::Yard => "yd",
+            Unit::Mile => "mi",
+            Unit::Ounce => "oz",
+            Unit::Pound => "lb",
+            Unit::Pint => "pt",
+            Unit::Quart => "qt",
+            Unit::Gallon => "gal",
+        }
+    }
+
+    pub fn family(&self) -> UnitFamily {
+        match self {
+            Unit::Millimeter
+            | Unit::Centimeter
+            | Unit::Decimeter
+            | Unit::Meter
+            | Unit::Kilometer
+            | Unit::Inch
+            | Unit::Foot
+            | Unit::Yard
+            | Unit::Mile => UnitFamily::Length,
+            Unit::Gram | Unit::Kilogram | Unit::Tonne | Unit::Ounce | Unit::Pound => {
+                UnitFamily::Mass
+            }
+            Unit::Milliliter | Unit::Liter | Unit::Pint | Unit::Quart | Unit::Gallon => {
+                UnitFamily::Volume
+            }
+        }
+    }
+
+    pub fn is_imperial(&self) -> bool {
+        matches!(
+            self,
+            Unit::Inch
+                | Unit::Foot
+                | Unit::Yard
+                | Unit::Mile
+                | Unit::Ounce
+                | Unit::Pound
+                | Unit::Pint
+                | Unit::Quart
+                | Unit::Gallon
+        )
+    }
+
+    /// Size in the smallest unit of the family and system, i.e. millimeters, grams,
+    /// milliliters, inches, ounces or pints.
+    pub fn size(&self) -> i64 {
+        match self {
+            Unit::Millimeter | Unit::Gram | Unit::Milliliter => 1,
+            Unit::Centimeter => 10,
+            Unit::Decimeter => 100,
+            Unit::Meter | Unit::Kilogram | Unit::Liter => 1000,
+            Unit::Kilometer | Unit::Tonne => 1_000_000,
+            Unit::Inch | Unit::Ounce | Unit::Pint => 1,
+            Unit::Foot => 12,
+            Unit::Yard => 36,
+            Unit::Mile => 63_360,
+            Unit::Pound => 16,
+            Unit::Quart => 2,
+            Unit::Gallon => 8,
+        }
+    }
+}
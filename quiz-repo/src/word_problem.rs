@@ -0,0 +1,335 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{expr::Expr, rational::Rational};
+
+/// Draws a template gets to find numbers meeting its constraints, when it's loaded
+/// and for each question.
+const MAX_TRIES: usize = 1000;
+
+/// A word problem with slots filled in at random, e.g.
+///
+/// ```json
+/// {
+///     "name": "buy_more",
+///     "grades": [1, 2],
+///     "text": {
+///         "en": "{name} has {a} apples and buys {b} more. How many apples are there now?",
+///         "zh": "{name}有{a}个苹果，又买了{b}个。现在一共有多少个苹果？"
+///     },
+///     "slots": {
+///         "name": { "type": "word", "values": { "en": ["Mia", "Tom"], "zh": ["小红", "小明"] } },
+///         "a": { "type": "integer", "min": 1, "max": 20 },
+///         "b": { "type": "integer", "min": 1, "max": 20 }
+///     },
+///     "constraints": ["answer <= 30"],
+///     "answer": "a + b"
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordProblemTemplate {
+    /// Unique name, stored with the generated questions.
+    pub name: String,
+    /// School grades the template suits, empty for every grade.
+    #[serde(default)]
+    pub grades: Vec<u8>,
+    /// The text in each locale, `{slot}` is replaced with the value of the slot.
+    pub text: BTreeMap<String, String>,
+    pub slots: BTreeMap<String, Slot>,
+    /// Conditions on the integer slots and the `answer`, e.g. `a > b` or `answer <= 100`.
+    /// The sides are arithmetic like the answer, compared with `<`, `<=`, `=`, `!=`,
+    /// `>=` or `>`.
+    #[serde(default)]
+    pub constraints: Vec<String>,
+    /// Arithmetic on the integer slots, e.g. `(a + b) * c`, multiplication is written `*`.
+    /// Draws with answers that aren't integers are skipped, e.g. `a / b` needs `b` to
+    /// divide `a`.
+    pub answer: String,
+}
+
+/// A placeholder in the text of a template.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Slot {
+    /// An integer drawn from `min..=max`.
+    Integer { min: i64, max: i64 },
+    /// A word picked from a list, e.g. a name, with one list per locale.
+    /// The lists are translations of each other, in the same order.
+    Word {
+        values: BTreeMap<String, Vec<String>>,
+    },
+}
+
+impl WordProblemTemplate {
+    /// Parses a JSON array of templates and checks every one of them.
+    pub fn parse(json: &str) -> anyhow::Result<Vec<Self>> {
+        let templates: Vec<Self> = serde_json::from_str(json)?;
+        for template in &templates {
+            template
+                .validate()
+                .map_err(|e| e.context(format!("Invalid template {}", template.name)))?;
+        }
+        Ok(templates)
+    }
+
+    /// Reads templates from a JSON file, see `parse`.
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<Self>> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// The templates shipped with the crate, in English and Chinese.
+    pub fn builtin() -> Vec<Self> {
+        Self::parse(include_str!("../word_problems.json")).expect("Built-in templates are valid")
+    }
+
+    /// Whether the template suits the school grade.
+    pub fn suits(&self, grade: u8) -> bool {
+        self.grades.is_empty() || self.grades.contains(&grade)
+    }
+
+    /// Draws until the constraints are met, returns the text in the locale and the answer.
+    /// Loaded templates are known to meet their constraints with some draws, but rarely
+    /// met constraints can still miss all of them.
+    pub(crate) fn generate<R: Rng + ?Sized>(
+        &self,
+        locale: &str,
+        rng: &mut R,
+    ) -> anyhow::Result<(String, i64)> {
+        for _ in 0..MAX_TRIES {
+            if let Some(problem) = self.try_generate(locale, rng)? {
+                return Ok(problem);
+            }
+        }
+        anyhow::bail!(
+            "No numbers meet the constraints of {} after {} draws",
+            self.name,
+            MAX_TRIES
+        )
+    }
+
+    /// The locales of the text and the word lists.
+    fn locales(&self) -> BTreeSet<&str> {
+        let words = self.slots.values().flat_map(|slot| match slot {
+            Slot::Integer { .. } => None,
+            Slot::Word { values } => Some(values.keys()),
+        });
+        self.text
+            .keys()
+            .chain(words.flatten())
+            .map(String::as_str)
+            .collect()
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.text.is_empty() {
+            anyhow::bail!("No text");
+        }
+        for text in self.text.values() {
+            for slot in placeholders(text) {
+                if !self.slots.contains_key(slot) {
+                    anyhow::bail!("Unknown slot {} in the text", slot);
+                }
+            }
+        }
+        for (name, slot) in &self.slots {
+            match slot {
+                Slot::Integer { min, max } if min > max => {
+                    anyhow::bail!("Slot {} has an empty range", name)
+                }
+                Slot::Word { values } => {
+                    let mut lengths = values.values().map(|words| words.len());
+                    let len = lengths.next().unwrap_or_default();
+                    if len == 0 || lengths.any(|other| other != len) {
+                        anyhow::bail!("Slot {} needs lists of the same length", name);
+                    }
+                }
+                _ => {}
+            }
+        }
+        for locale in self.locales() {
+            self.generate(locale, &mut ChaCha8Rng::seed_from_u64(0))
+                .map_err(|e| e.context(format!("In locale {}", locale)))?;
+        }
+        Ok(())
+    }
+
+    /// One draw, `None` if it doesn't meet the constraints or has no integer answer.
+    fn try_generate<R: Rng + ?Sized>(
+        &self,
+        locale: &str,
+        rng: &mut R,
+    ) -> anyhow::Result<Option<(String, i64)>> {
+        let mut numbers = BTreeMap::new();
+        let mut words = BTreeMap::new();
+        for (name, slot) in &self.slots {
+            match slot {
+                Slot::Integer { min, max } => {
+                    numbers.insert(name.as_str(), rng.gen_range(*min..=*max));
+                }
+                Slot::Word { values } => {
+                    let list = localized(values, locale);
+                    words.insert(name.as_str(), list[rng.gen_range(0..list.len())].as_str());
+                }
+            }
+        }
+        let Some(answer) = evaluate(&self.answer, &numbers)?.and_then(|value| value.to_integer())
+        else {
+            return Ok(None);
+        };
+        numbers.insert("answer", answer);
+        for constraint in &self.constraints {
+            if !holds(constraint, &numbers)? {
+                return Ok(None);
+            }
+        }
+        let mut text = localized(&self.text, locale).clone();
+        for (name, value) in numbers {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        for (name, word) in words {
+            text = text.replace(&format!("{{{}}}", name), word);
+        }
+        Ok(Some((text, answer)))
+    }
+}
+
+/// The value for the locale, falls back to English and then to any locale.
+fn localized<'a, T>(values: &'a BTreeMap<String, T>, locale: &str) -> &'a T {
+    values
+        .get(locale)
+        .or_else(|| values.get("en"))
+        .or_else(|| values.values().next())
+        .expect("Templates are validated to have every locale map filled")
+}
+
+/// Names of the `{slot}` placeholders in the text.
+fn placeholders(text: &str) -> impl Iterator<Item = &str> {
+    text.split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+}
+
+/// Substitutes the numbers into the formula, `None` if it has no value, e.g. dividing by 0.
+fn evaluate(formula: &str, numbers: &BTreeMap<&str, i64>) -> anyhow::Result<Option<Rational>> {
+    let mut substituted = String::new();
+    let mut chars = formula.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !c.is_ascii_alphabetic() && c != '_' {
+            substituted.push(c);
+            continue;
+        }
+        let mut name = c.to_string();
+        while let Some(&c) = chars.peek() {
+            if !c.is_ascii_alphanumeric() && c != '_' {
+                break;
+            }
+            name.push(c);
+            chars.next();
+        }
+        let value = numbers
+            .get(name.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Unknown integer slot {} in {}", name, formula))?;
+        // Negative numbers in parentheses, e.g. `a - b` with `b = -3` is `5 - (-3)`
+        substituted.push_str(&format!("({})", value));
+    }
+    Ok(substituted.parse::<Expr>()?.eval())
+}
+
+/// Whether the constraint holds for the numbers.
+fn holds(constraint: &str, numbers: &BTreeMap<&str, i64>) -> anyhow::Result<bool> {
+    let position = constraint
+        .find(['<', '>', '=', '!'])
+        .ok_or_else(|| anyhow::anyhow!("No comparison in constraint {}", constraint))?;
+    let (lhs, rest) = constraint.split_at(position);
+    let operator_len = if rest[1..].starts_with('=') { 2 } else { 1 };
+    let (operator, rhs) = rest.split_at(operator_len);
+    let (Some(lhs), Some(rhs)) = (evaluate(lhs, numbers)?, evaluate(rhs, numbers)?) else {
+        return Ok(false);
+    };
+    Ok(match operator {
+        "<" => lhs < rhs,
+        "<=" => lhs <= rhs,
+        "=" | "==" => lhs == rhs,
+        "!=" => lhs != rhs,
+        ">=" => lhs >= rhs,
+        ">" => lhs > rhs,
+        _ => anyhow::bail!(
+            "Unknown comparison {} in constraint {}",
+            operator,
+            constraint
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IMPOSSIBLE: &str = r#"[{
+        "name": "impossible",
+        "text": { "en": "What is {a} and 10?", "zh": "{a}加10是多少？" },
+        "slots": { "a": { "type": "integer", "min": 1, "max": 5 } },
+        "constraints": ["answer > 20"],
+        "answer": "a + 10"
+    }]"#;
+
+    #[test]
+    fn builtin_templates_fill_every_locale() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        for template in WordProblemTemplate::builtin() {
+            for locale in template.locales() {
+                let (text, _) = template.generate(locale, &mut rng).unwrap();
+                assert!(
+                    !text.contains('{'),
+                    "{} in {}: {}",
+                    template.name,
+                    locale,
+                    text
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_impossible_constraints() {
+        let e = WordProblemTemplate::parse(IMPOSSIBLE).unwrap_err();
+        assert!(format!("{:#}", e).contains("impossible"));
+    }
+
+    #[test]
+    fn gives_up_on_impossible_constraints() {
+        let templates: Vec<WordProblemTemplate> = serde_json::from_str(IMPOSSIBLE).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert!(templates[0].generate("en", &mut rng).is_err());
+    }
+
+    #[test]
+    fn locales_of_text_and_words() {
+        let template: WordProblemTemplate = serde_json::from_str(
+            r#"{
+                "name": "share",
+                "text": { "en": "{name} shares {a} sweets with 2 friends." },
+                "slots": {
+                    "name": { "type": "word", "values": { "en": ["Mia"], "fr": ["Léa"] } },
+                    "a": { "type": "integer", "min": 1, "max": 30 }
+                },
+                "answer": "a / 3"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(template.locales(), BTreeSet::from(["en", "fr"]));
+        let (text, answer) = template
+            .generate("fr", &mut ChaCha8Rng::seed_from_u64(0))
+            .unwrap();
+        assert_eq!(
+            text,
+            format!("Léa shares {} sweets with 2 friends.", answer * 3)
+        );
+    }
+}
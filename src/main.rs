@@ -2,7 +2,7 @@ use std::{env, path::PathBuf, time::Duration};
 
 use chrono::{Datelike, Utc};
 use chrono_tz::Tz;
-use clap::{command, Parser};
+use clap::Parser;
 use embed_spa::EmbeddedSPAEndpoint;
 use env_logger::Env;
 use log::{debug, info};
@@ -27,6 +27,9 @@ pub struct Files;
 #[derive(Clone)]
 struct AppState {
     timezone: String,
+    profile: quiz_repo::DifficultyProfile,
+    /// The generators built from `profile`
+    generators: quiz_repo::GeneratorRegistry,
     repo: quiz_repo::QuizRepo,
 }
 
@@ -34,12 +37,76 @@ struct AppState {
 struct QuestionResponse {
     id: i64,
     question: String,
-    answer: Option<i64>,
+    /// Kind of the question, "missing_operand" questions have a `?` in place of the answer
+    kind: &'static str,
+    answer: Option<String>,
+    /// Type of the answer, e.g. "integer" or "fraction"
+    answer_type: &'static str,
+    /// Wrong parts of the answer, e.g. "remainder", only set in the mistake collection
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    wrong_parts: Vec<&'static str>,
+    /// Answers may be negative, front ends show a key for the minus sign
+    negative_numbers: bool,
+    /// Answers to pick from in multiple choice mode, in the order to show them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    choices: Vec<String>,
+}
+
+impl From<quiz_repo::Question> for QuestionResponse {
+    fn from(question: quiz_repo::Question) -> Self {
+        Self {
+            id: question.get_id(),
+            question: question.get_question(),
+            kind: question.get_kind().name(),
+            answer: question.get_answer().map(|a| a.to_string()),
+            answer_type: question.get_expected_answer().kind(),
+            wrong_parts: question.wrong_parts(),
+            negative_numbers: false,
+            choices: vec![],
+        }
+    }
+}
+
+#[derive(Default, serde::Deserialize)]
+struct NewQuestionRequest {
+    /// Drill these times tables instead of using the configured profile, e.g. [6, 7, 8]
+    #[serde(default)]
+    times_tables: Vec<i64>,
+    /// Session to continue, the questions of a session are reproducible from its seed
+    session: Option<i64>,
+    /// Offer the answer as a choice between the correct one and typical errors
+    #[serde(default)]
+    multiple_choice: bool,
 }
 
 #[handler]
-async fn new_question(Data(state): Data<&AppState>) -> poem::Result<Json<QuestionResponse>> {
-    let question = state.repo.new_question().await.map_err(|e| {
+async fn new_question(
+    Data(state): Data<&AppState>,
+    req: Option<Json<NewQuestionRequest>>,
+) -> poem::Result<Json<QuestionResponse>> {
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+    let (profile, generators) = if req.times_tables.is_empty() {
+        (state.profile.clone(), state.generators.clone())
+    } else {
+        if req
+            .times_tables
+            .iter()
+            .any(|&table| !(1..=100).contains(&table))
+        {
+            return Err(poem::Error::from_string(
+                "Invalid times table",
+                StatusCode::BAD_REQUEST,
+            ));
+        }
+        let profile = quiz_repo::DifficultyProfile::times_tables(req.times_tables);
+        let generators = quiz_repo::GeneratorRegistry::from_profile(&profile);
+        (profile, generators)
+    };
+    let question = match req.session {
+        Some(session) => state.repo.new_session_question(session, &generators).await,
+        None => state.repo.new_question_from(&generators).await,
+    }
+    .map_err(|e| {
         log::error!("Error: {:?}", e);
         anyhow::Error::msg("Failed to create new question")
     })?;
@@ -48,17 +115,83 @@ async fn new_question(Data(state): Data<&AppState>) -> poem::Result<Json<Questio
         question.get_id(),
         question.get_question()
     );
+    let choices = if req.multiple_choice {
+        state
+            .repo
+            .multiple_choice(&question)
+            .await
+            .map_err(|e| {
+                log::error!("Error: {:?}", e);
+                anyhow::Error::msg("Failed to make choices")
+            })?
+            .0
+            .into_iter()
+            .map(|choice| choice.answer.to_string())
+            .collect()
+    } else {
+        vec![]
+    };
     Ok(Json(QuestionResponse {
-        id: question.get_id(),
-        question: question.get_question(),
-        answer: question.get_answer(),
+        negative_numbers: profile.negative_numbers,
+        choices,
+        ..question.into()
     }))
 }
 
+#[derive(Default, serde::Deserialize)]
+struct NewSessionRequest {
+    /// Seed of the questions, random if not given
+    seed: Option<u32>,
+}
+
+#[derive(serde::Serialize)]
+struct SessionResponse {
+    id: i64,
+    seed: u32,
+}
+
+#[handler]
+async fn new_session(
+    Data(state): Data<&AppState>,
+    req: Option<Json<NewSessionRequest>>,
+) -> poem::Result<Json<SessionResponse>> {
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+    let (id, seed) = state.repo.new_session(req.seed).await.map_err(|e| {
+        log::error!("Error: {:?}", e);
+        anyhow::Error::msg("Failed to create new session")
+    })?;
+    debug!("session: {}, seed: {}", id, seed);
+    Ok(Json(SessionResponse { id, seed }))
+}
+
+#[derive(serde::Serialize)]
+struct GeneratorResponse {
+    name: &'static str,
+    /// Relative chance of the generator, 0 if it's disabled
+    weight: u32,
+    parameters: Vec<quiz_repo::Parameter>,
+}
+
+#[handler]
+async fn get_generators(Data(state): Data<&AppState>) -> Json<Vec<GeneratorResponse>> {
+    Json(
+        state
+            .generators
+            .generators()
+            .map(|generator| GeneratorResponse {
+                name: generator.name(),
+                weight: generator.weight(),
+                parameters: generator.parameters(),
+            })
+            .collect(),
+    )
+}
+
 #[derive(serde::Deserialize)]
 struct SubmitAnswerRequest {
     id: i64,
-    answer: i64,
+    /// The user input, e.g. "42" or "3/4"
+    answer: String,
 }
 
 #[derive(serde::Serialize)]
@@ -75,7 +208,7 @@ async fn submit_answer(
     debug!("id: {}, answer: {}", req.id, req.answer);
     let ret = state
         .repo
-        .answer_question(req.id, req.answer)
+        .answer_question(req.id, &req.answer)
         .await
         .map_err(|e| {
             log::error!("Error: {:?}", e);
@@ -279,6 +412,99 @@ async fn get_daily_statistics(
         .map(|(correct, total)| Json(StatisticsResponse { correct, total }))?)
 }
 
+#[derive(serde::Serialize)]
+struct MisconceptionStatisticsResponse {
+    /// The typical error behind the picked choice, e.g. "missed_carry"
+    misconception: String,
+    count: i64,
+}
+
+#[handler]
+async fn get_misconception_statistics(
+    Data(state): Data<&AppState>,
+) -> poem::Result<Json<Vec<MisconceptionStatisticsResponse>>> {
+    let ret: Vec<MisconceptionStatisticsResponse> = state
+        .repo
+        .get_misconception_statistics()
+        .await
+        .map_err(|e| {
+            log::error!("Error: {:?}", e);
+            anyhow::Error::msg("Failed to get misconception statistics")
+        })?
+        .into_iter()
+        .map(|(misconception, count)| MisconceptionStatisticsResponse {
+            misconception,
+            count,
+        })
+        .collect();
+    Ok(Json(ret))
+}
+
+#[derive(serde::Serialize)]
+struct RegroupingStatisticsResponse {
+    /// "carry" or "borrow"
+    regrouping: String,
+    /// Number of carries or borrows in the question
+    regroupings: i64,
+    correct: i64,
+    total: i64,
+}
+
+#[handler]
+async fn get_regrouping_statistics(
+    Data(state): Data<&AppState>,
+) -> poem::Result<Json<Vec<RegroupingStatisticsResponse>>> {
+    let ret: Vec<RegroupingStatisticsResponse> = state
+        .repo
+        .get_regrouping_statistics()
+        .await
+        .map_err(|e| {
+            log::error!("Error: {:?}", e);
+            anyhow::Error::msg("Failed to get regrouping statistics")
+        })?
+        .into_iter()
+        .map(
+            |(regrouping, regroupings, correct, total)| RegroupingStatisticsResponse {
+                regrouping,
+                regroupings,
+                correct,
+                total,
+            },
+        )
+        .collect();
+    Ok(Json(ret))
+}
+
+#[derive(serde::Serialize)]
+struct UnitStatisticsResponse {
+    /// "length", "mass" or "volume"
+    unit_family: String,
+    correct: i64,
+    total: i64,
+}
+
+#[handler]
+async fn get_unit_statistics(
+    Data(state): Data<&AppState>,
+) -> poem::Result<Json<Vec<UnitStatisticsResponse>>> {
+    let ret: Vec<UnitStatisticsResponse> = state
+        .repo
+        .get_unit_statistics()
+        .await
+        .map_err(|e| {
+            log::error!("Error: {:?}", e);
+            anyhow::Error::msg("Failed to get unit statistics")
+        })?
+        .into_iter()
+        .map(|(unit_family, correct, total)| UnitStatisticsResponse {
+            unit_family,
+            correct,
+            total,
+        })
+        .collect();
+    Ok(Json(ret))
+}
+
 #[handler]
 async fn get_mistake_collection(
     Data(state): Data<&AppState>,
@@ -292,11 +518,7 @@ async fn get_mistake_collection(
             anyhow::Error::msg("Failed to get mistake collection")
         })?
         .into_iter()
-        .map(|(id, question, answer)| QuestionResponse {
-            id,
-            question,
-            answer,
-        })
+        .map(QuestionResponse::from)
         .collect();
     debug!("mistake_collection contains {} items", ret.len());
     Ok(Json(ret))
@@ -313,6 +535,34 @@ struct Args {
     #[arg(long, default_value = "Asia/Shanghai")]
     timezone: String,
 
+    /// School grade (1-6) used to pick the question difficulty
+    #[arg(short, long)]
+    grade: Option<u8>,
+
+    /// Include negative numbers in the integer questions
+    #[arg(long)]
+    negative: bool,
+
+    /// Number of carries in addition: "none", "one" or "multiple"
+    #[arg(long)]
+    carries: Option<quiz_repo::RegroupingCount>,
+
+    /// Number of borrows in subtraction: "none", "one" or "multiple"
+    #[arg(long)]
+    borrows: Option<quiz_repo::RegroupingCount>,
+
+    /// Add imperial units, e.g. "ft" and "in", to the unit conversions
+    #[arg(long)]
+    imperial: bool,
+
+    /// JSON file with word problem templates, replaces the built-in ones
+    #[arg(long)]
+    word_problems: Option<PathBuf>,
+
+    /// Language of the word problems and currency of the money questions, e.g. "en" or "zh"
+    #[arg(long, default_value = "en")]
+    locale: String,
+
     /// Database path, default to "questions.db" under the current directory
     #[arg(short, long)]
     database: Option<PathBuf>,
@@ -346,16 +596,40 @@ async fn main() -> anyhow::Result<()> {
             .unwrap_or_else(|_| "questions.db".to_string())
             .into()
     });
+    let mut profile = quiz_repo::DifficultyProfile {
+        negative_numbers: args.negative,
+        carries: args.carries,
+        borrows: args.borrows,
+        ..args
+            .grade
+            .map(quiz_repo::DifficultyProfile::grade)
+            .unwrap_or_default()
+    };
+    if let Some(path) = &args.word_problems {
+        profile.word_problem.templates = quiz_repo::WordProblemTemplate::load(path)?;
+        profile.word_problem.weight = profile.word_problem.weight.max(1);
+    }
+    profile.word_problem.locale = args.locale.clone();
+    profile.money.currency = quiz_repo::Currency::from_locale(&args.locale);
+    profile.unit.imperial = args.imperial;
+    profile.validate()?;
     let state = AppState {
         timezone: args.timezone.clone(),
+        generators: quiz_repo::GeneratorRegistry::from_profile(&profile),
+        profile,
         repo: quiz_repo::QuizRepo::new(Some(&db_path)).await?,
     };
 
     let app = Route::new()
+        .at("/api/new-session", post(new_session))
         .at("/api/new-question", post(new_question))
         .at("/api/submit-answer", post(submit_answer))
+        .at("/api/generators", get_generators)
         .at("/api/statistics", get_statistics)
         .at("/api/mistake-collection", get_mistake_collection)
+        .at("/api/regrouping", get_regrouping_statistics)
+        .at("/api/misconceptions", get_misconception_statistics)
+        .at("/api/units", get_unit_statistics)
         .at("/api/today", today_statistics)
         .at("/api/last7", last7_statistics)
         .at("/api/last30", last30_statistics)